@@ -10,5 +10,10 @@ struct LineMaterial {
 fn fragment(
     mesh: VertexOutput,
 ) -> @location(0) vec4<f32> {
+#ifdef VERTEX_COLORS
+    // Per-vertex colors are interpolated along each segment and tinted by the material
+    return material.color * mesh.color;
+#else
     return material.color;
+#endif
 }
@@ -0,0 +1,240 @@
+//! Adaptive step size integration with the Dormand–Prince 5(4) embedded Runge-Kutta pair
+//!
+//! Each step produces a fifth order solution together with a fourth order one, their
+//! difference estimates the local error and is used to grow or shrink the step size. The
+//! solution between accepted steps is recovered with the method's fourth order dense output,
+//! so the trajectory can be sampled at whatever times the caller wants.
+
+use glam::DVec3;
+
+// The Butcher tableau, the time nodes are left out as the flows are autonomous. The last
+// stage is evaluated at the new point so it doubles as the first stage of the next step
+const A21: f64 = 1. / 5.;
+const A31: f64 = 3. / 40.;
+const A32: f64 = 9. / 40.;
+const A41: f64 = 44. / 45.;
+const A42: f64 = -56. / 15.;
+const A43: f64 = 32. / 9.;
+const A51: f64 = 19372. / 6561.;
+const A52: f64 = -25360. / 2187.;
+const A53: f64 = 64448. / 6561.;
+const A54: f64 = -212. / 729.;
+const A61: f64 = 9017. / 3168.;
+const A62: f64 = -355. / 33.;
+const A63: f64 = 46732. / 5247.;
+const A64: f64 = 49. / 176.;
+const A65: f64 = -5103. / 18656.;
+const A71: f64 = 35. / 384.;
+const A73: f64 = 500. / 1113.;
+const A74: f64 = 125. / 192.;
+const A75: f64 = -2187. / 6784.;
+const A76: f64 = 11. / 84.;
+
+// Difference between the fifth and fourth order weights
+const E1: f64 = 71. / 57600.;
+const E3: f64 = -71. / 16695.;
+const E4: f64 = 71. / 1920.;
+const E5: f64 = -17253. / 339200.;
+const E6: f64 = 22. / 525.;
+const E7: f64 = -1. / 40.;
+
+// Dense output weights
+const D1: f64 = -12715105075. / 11282082432.;
+const D3: f64 = 87487479700. / 32700410799.;
+const D4: f64 = -10690763975. / 1880347072.;
+const D5: f64 = 701980252875. / 199316789632.;
+const D6: f64 = -1453857185. / 822651844.;
+const D7: f64 = 69997945. / 29380423.;
+
+/// The smallest step size allowed, as a fraction of the current time once that is past one
+const MIN_STEP: f64 = 1e-12;
+
+/// Step size control keeps the estimated error of every step below
+/// `absolute + relative * |y|` in each component
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    pub absolute: f64,
+    pub relative: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            absolute: 1e-9,
+            relative: 1e-9,
+        }
+    }
+}
+
+/// How many steps were kept and how many were retried with a smaller step size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StepStats {
+    pub accepted: usize,
+    pub rejected: usize,
+}
+
+/// An accepted step, which can be evaluated at any time it covers
+#[derive(Debug, Clone, Copy)]
+pub struct DenseStep {
+    pub t0: f64,
+    pub h: f64,
+    coefficients: [DVec3; 5],
+}
+
+impl DenseStep {
+    pub fn end(&self) -> f64 {
+        self.t0 + self.h
+    }
+
+    /// The interpolated solution at `t`, which should lie within `t0..=t0 + h`
+    pub fn evaluate(&self, t: f64) -> DVec3 {
+        let theta = (t - self.t0) / self.h;
+        let theta1 = 1. - theta;
+        let [r1, r2, r3, r4, r5] = self.coefficients;
+
+        r1 + theta * (r2 + theta1 * (r3 + theta * (r4 + theta1 * r5)))
+    }
+}
+
+/// The derivatives at each stage of a step of size `h` from `y`, and the fifth order solution
+fn stages(f: impl Fn(DVec3) -> DVec3, y: DVec3, k1: DVec3, h: f64) -> ([DVec3; 7], DVec3) {
+    let k2 = f(y + h * A21 * k1);
+    let k3 = f(y + h * (A31 * k1 + A32 * k2));
+    let k4 = f(y + h * (A41 * k1 + A42 * k2 + A43 * k3));
+    let k5 = f(y + h * (A51 * k1 + A52 * k2 + A53 * k3 + A54 * k4));
+    let k6 = f(y + h * (A61 * k1 + A62 * k2 + A63 * k3 + A64 * k4 + A65 * k5));
+    let y1 = y + h * (A71 * k1 + A73 * k3 + A74 * k4 + A75 * k5 + A76 * k6);
+    let k7 = f(y1);
+
+    ([k1, k2, k3, k4, k5, k6, k7], y1)
+}
+
+/// A single step of the fifth order solution without any error control
+pub(crate) fn fixed_step(f: impl Fn(DVec3) -> DVec3, y: DVec3, h: f64) -> DVec3 {
+    let k1 = f(y);
+    stages(f, y, k1, h).1
+}
+
+/// The Dormand–Prince integrator, following a single solution of `dp/dt = f(p)`
+#[derive(Debug, Clone)]
+pub struct DormandPrince {
+    pub tolerance: Tolerance,
+    pub stats: StepStats,
+    /// The size of the next step to attempt
+    h: f64,
+    t: f64,
+    y: DVec3,
+    /// The derivative at `y`, carried over from the last stage of the previous step
+    k1: Option<DVec3>,
+    last: Option<DenseStep>,
+    sampled: Option<(f64, DVec3)>,
+}
+
+impl DormandPrince {
+    pub fn new(tolerance: Tolerance) -> Self {
+        Self {
+            tolerance,
+            stats: StepStats::default(),
+            h: 1e-3,
+            t: 0.,
+            y: DVec3::ZERO,
+            k1: None,
+            last: None,
+            sampled: None,
+        }
+    }
+
+    /// Restarts the integration from `y` at time `t`
+    pub fn reset(&mut self, t: f64, y: DVec3) {
+        self.t = t;
+        self.y = y;
+        self.k1 = None;
+        self.last = None;
+        self.sampled = Some((t, y));
+    }
+
+    /// Continues from the previous sample if it was `y` at `t`, otherwise starts again from there
+    pub fn resume(&mut self, t: f64, y: DVec3) {
+        if self.sampled != Some((t, y)) {
+            self.reset(t, y);
+        }
+    }
+
+    /// Takes steps until one meets the tolerance and returns it
+    ///
+    /// Fails when the solution stops being finite, or when the step size has to shrink to
+    /// next to nothing compared to the time to meet the tolerance, as it does where the
+    /// solution blows up in finite time.
+    pub fn step(&mut self, f: impl Fn(DVec3) -> DVec3) -> Result<DenseStep, String> {
+        let (t, y) = (self.t, self.y);
+        let k1 = self.k1.unwrap_or_else(|| f(y));
+        let smallest = MIN_STEP * t.abs().max(1.);
+
+        loop {
+            let h = self.h;
+            let ([_, _, k3, k4, k5, k6, k7], y1) = stages(&f, y, k1, h);
+
+            let error = h * (E1 * k1 + E3 * k3 + E4 * k4 + E5 * k5 + E6 * k6 + E7 * k7);
+            let scale = DVec3::splat(self.tolerance.absolute)
+                + self.tolerance.relative * y.abs().max(y1.abs());
+            let norm = ((error / scale).length_squared() / 3.).sqrt();
+            if !norm.is_finite() || !k1.is_finite() {
+                return Err(format!("the solution stopped being finite at t = {t}"));
+            }
+
+            // Aim a little below the tolerance and never change the step size too abruptly
+            let factor = (0.9 * norm.powf(-1. / 5.)).clamp(0.2, 5.);
+
+            if norm <= 1. {
+                self.stats.accepted += 1;
+                self.h = h * factor;
+
+                let ydiff = y1 - y;
+                let bspl = h * k1 - ydiff;
+                let step = DenseStep {
+                    t0: t,
+                    h,
+                    coefficients: [
+                        y,
+                        ydiff,
+                        bspl,
+                        ydiff - h * k7 - bspl,
+                        h * (D1 * k1 + D3 * k3 + D4 * k4 + D5 * k5 + D6 * k6 + D7 * k7),
+                    ],
+                };
+
+                self.t = t + h;
+                self.y = y1;
+                self.k1 = Some(k7);
+                self.last = Some(step);
+                return Ok(step);
+            }
+
+            self.stats.rejected += 1;
+            self.h = h * factor.min(1.);
+            if self.h < smallest {
+                return Err(format!(
+                    "the step size fell below {smallest:e} at t = {t}, the solution may blow up \
+                     there"
+                ));
+            }
+        }
+    }
+
+    /// Integrates forward until `t` is reached and returns the solution there, failing when
+    /// a step does
+    pub fn sample(&mut self, f: impl Fn(DVec3) -> DVec3, t: f64) -> Result<DVec3, String> {
+        let step = loop {
+            match self.last {
+                Some(step) if step.end() >= t => break step,
+                _ => {
+                    self.step(&f)?;
+                }
+            }
+        };
+
+        let y = step.evaluate(t);
+        self.sampled = Some((t, y));
+        Ok(y)
+    }
+}
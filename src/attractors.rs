@@ -0,0 +1,406 @@
+//! The built-in catalogue of chaotic flows
+//!
+//! Each system comes with the parameters it is usually drawn with, a starting point that
+//! settles onto its attractor quickly and a view that fits the attractor on screen.
+
+use std::{fmt, str::FromStr};
+
+use glam::{DMat3, DVec3};
+
+use crate::{
+    equilibria,
+    system::{numerical_jacobian, DynamicalSystem, Framing},
+};
+
+/// The equilibria of a system for the given parameters
+type Equilibria = fn(&[f64]) -> Vec<DVec3>;
+
+/// Everything known about a built-in system apart from its current parameters
+struct Spec {
+    name: &'static str,
+    /// The name and default value of each parameter
+    params: &'static [(&'static str, f64)],
+    initial: DVec3,
+    dt: f64,
+    framing: Framing,
+    derivative: fn(&[f64], DVec3) -> DVec3,
+    /// The exact Jacobian of `derivative`, otherwise it is found by central differences
+    jacobian: Option<fn(&[f64], DVec3) -> DMat3>,
+    /// The equilibria for the parameters, otherwise they are searched for
+    equilibria: Option<Equilibria>,
+}
+
+impl fmt::Debug for Spec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+impl PartialEq for Spec {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+/// A camera looking at `look_at` from `position`, colouring `x` and `y` over the given ranges
+const fn framing(position: [f64; 3], look_at: [f64; 3], x: [f64; 2], y: [f64; 2]) -> Framing {
+    Framing {
+        position: DVec3::from_array(position),
+        look_at: DVec3::from_array(look_at),
+        x,
+        y,
+    }
+}
+
+/// Unpacks the parameters of a system, which always has as many as its spec lists
+fn unpack<const N: usize>(params: &[f64]) -> [f64; N] {
+    params.try_into().expect("wrong number of parameters")
+}
+
+static CATALOGUE: [Spec; 12] = [
+    Spec {
+        name: "lorenz",
+        params: &[("sigma", 10.), ("rho", 28.), ("beta", 8. / 3.)],
+        initial: DVec3::new(0.1, 0., 0.1),
+        dt: 0.001,
+        framing: framing([-100., 0., 150.], [0., 0., 0.], [-13., 13.], [-28., 28.]),
+        derivative: |params, p| {
+            let [sigma, rho, beta] = unpack(params);
+            DVec3::new(
+                sigma * (p.y - p.x),
+                p.x * (rho - p.z) - p.y,
+                p.x * p.y - beta * p.z,
+            )
+        },
+        jacobian: Some(|params, p| {
+            let [sigma, rho, beta] = unpack(params);
+            DMat3::from_cols(
+                DVec3::new(-sigma, rho - p.z, p.y),
+                DVec3::new(sigma, -1., p.x),
+                DVec3::new(0., -p.x, -beta),
+            )
+        }),
+        // The origin, and the centres of the two wings once rho is above 1
+        equilibria: Some(|params| {
+            let [_, rho, beta] = unpack(params);
+            let mut equilibria = vec![DVec3::ZERO];
+            if rho > 1. {
+                let r = (beta * (rho - 1.)).sqrt();
+                equilibria.extend([1., -1.].map(|side| DVec3::new(side * r, side * r, rho - 1.)));
+            }
+            equilibria
+        }),
+    },
+    Spec {
+        name: "rossler",
+        params: &[("a", 0.2), ("b", 0.2), ("c", 5.7)],
+        initial: DVec3::new(1., 1., 1.),
+        dt: 0.005,
+        framing: framing([-45., 0., 80.], [1., -1., 10.], [-6., 8.], [-8., 5.]),
+        derivative: |params, p| {
+            let [a, b, c] = unpack(params);
+            DVec3::new(-p.y - p.z, p.x + a * p.y, b + p.z * (p.x - c))
+        },
+        jacobian: None,
+        equilibria: None,
+    },
+    Spec {
+        name: "chen",
+        params: &[("a", 35.), ("b", 3.), ("c", 28.)],
+        initial: DVec3::new(-0.1, 0.5, -0.6),
+        dt: 0.001,
+        framing: framing([-115., 0., 200.], [0., 0., 27.], [-17., 17.], [-20., 20.]),
+        derivative: |params, p| {
+            let [a, b, c] = unpack(params);
+            DVec3::new(
+                a * (p.y - p.x),
+                (c - a) * p.x - p.x * p.z + c * p.y,
+                p.x * p.y - b * p.z,
+            )
+        },
+        jacobian: None,
+        equilibria: None,
+    },
+    Spec {
+        name: "lu",
+        params: &[("a", 36.), ("b", 3.), ("c", 20.)],
+        initial: DVec3::new(0.1, 0.3, -0.6),
+        dt: 0.001,
+        framing: framing([-90., 0., 160.], [0., 0., 22.], [-14., 14.], [-16., 16.]),
+        derivative: |params, p| {
+            let [a, b, c] = unpack(params);
+            DVec3::new(a * (p.y - p.x), -p.x * p.z + c * p.y, p.x * p.y - b * p.z)
+        },
+        jacobian: None,
+        equilibria: None,
+    },
+    Spec {
+        name: "aizawa",
+        params: &[
+            ("a", 0.95),
+            ("b", 0.7),
+            ("c", 0.6),
+            ("d", 3.5),
+            ("e", 0.25),
+            ("f", 0.1),
+        ],
+        initial: DVec3::new(0.1, 0., 0.),
+        dt: 0.005,
+        framing: framing([-6., 0., 10.], [0., 0., 0.75], [-1., 1.], [-1., 1.]),
+        derivative: |params, p| {
+            let [a, b, c, d, e, f] = unpack(params);
+            DVec3::new(
+                (p.z - b) * p.x - d * p.y,
+                d * p.x + (p.z - b) * p.y,
+                c + a * p.z - p.z.powi(3) / 3. - (p.x * p.x + p.y * p.y) * (1. + e * p.z)
+                    + f * p.z * p.x.powi(3),
+            )
+        },
+        jacobian: None,
+        equilibria: None,
+    },
+    Spec {
+        name: "thomas",
+        params: &[("b", 0.208186)],
+        initial: DVec3::new(1.1, 1.1, -0.01),
+        dt: 0.01,
+        framing: framing(
+            [-9., 1.5, 16.],
+            [1.35, 1.35, 1.35],
+            [-0.4, 3.1],
+            [-0.4, 3.1],
+        ),
+        derivative: |params, p| {
+            let [b] = unpack(params);
+            DVec3::new(
+                p.y.sin() - b * p.x,
+                p.z.sin() - b * p.y,
+                p.x.sin() - b * p.z,
+            )
+        },
+        jacobian: None,
+        equilibria: None,
+    },
+    Spec {
+        name: "halvorsen",
+        params: &[("a", 1.89)],
+        initial: DVec3::new(-1.48, -1.51, 2.04),
+        dt: 0.002,
+        framing: framing([-40., -3., 52.], [-3., -3., -3.], [-9., 3.5], [-9., 3.5]),
+        derivative: |params, p| {
+            let [a] = unpack(params);
+            DVec3::new(
+                -a * p.x - 4. * p.y - 4. * p.z - p.y * p.y,
+                -a * p.y - 4. * p.z - 4. * p.x - p.z * p.z,
+                -a * p.z - 4. * p.x - 4. * p.y - p.x * p.x,
+            )
+        },
+        jacobian: None,
+        equilibria: None,
+    },
+    Spec {
+        name: "dadras",
+        params: &[("a", 3.), ("b", 2.7), ("c", 1.7), ("d", 2.), ("e", 9.)],
+        initial: DVec3::new(1.1, 2.1, -2.),
+        dt: 0.002,
+        framing: framing([-63., -2., 97.], [3., -2., -2.], [-9., 14.], [-8.5, 4.5]),
+        derivative: |params, p| {
+            let [a, b, c, d, e] = unpack(params);
+            DVec3::new(
+                p.y - a * p.x + b * p.y * p.z,
+                c * p.y - p.x * p.z + p.z,
+                d * p.x * p.y - e * p.z,
+            )
+        },
+        jacobian: None,
+        equilibria: None,
+    },
+    Spec {
+        name: "sprott",
+        params: &[("a", 2.07), ("b", 1.79)],
+        initial: DVec3::new(0.63, 0.47, -0.54),
+        dt: 0.005,
+        framing: framing([-3.7, 0., 6.6], [0.7, 0., 0.], [0., 1.4], [-0.75, 0.75]),
+        derivative: |params, p| {
+            let [a, b] = unpack(params);
+            DVec3::new(
+                p.y + a * p.x * p.y + p.x * p.z,
+                1. - b * p.x * p.x + p.y * p.z,
+                p.x - p.x * p.x - p.y * p.y,
+            )
+        },
+        jacobian: None,
+        equilibria: None,
+    },
+    Spec {
+        name: "rabinovich-fabrikant",
+        params: &[("alpha", 0.14), ("gamma", 0.1)],
+        initial: DVec3::new(-1., 0., 0.5),
+        dt: 0.002,
+        framing: framing([-6.5, 0., 8.], [-1.5, 0.1, 0.5], [-1.9, -1.1], [-0.8, 1.]),
+        derivative: |params, p| {
+            let [alpha, gamma] = unpack(params);
+            DVec3::new(
+                p.y * (p.z - 1. + p.x * p.x) + gamma * p.x,
+                p.x * (3. * p.z + 1. - p.x * p.x) + gamma * p.y,
+                -2. * p.z * (alpha + p.x * p.y),
+            )
+        },
+        jacobian: None,
+        equilibria: None,
+    },
+    Spec {
+        name: "four-wing",
+        params: &[("a", 0.2), ("b", 0.01), ("c", -0.4)],
+        initial: DVec3::new(1.3, -0.18, 0.01),
+        dt: 0.005,
+        framing: framing([-8., 0., 12.], [0., 0., -0.3], [-1.4, 1.4], [-1.3, 1.3]),
+        derivative: |params, p| {
+            let [a, b, c] = unpack(params);
+            DVec3::new(
+                a * p.x + p.y * p.z,
+                b * p.x + c * p.y - p.x * p.z,
+                -p.z - p.x * p.y,
+            )
+        },
+        jacobian: None,
+        equilibria: None,
+    },
+    Spec {
+        name: "chua",
+        params: &[
+            ("alpha", 15.6),
+            ("beta", 28.),
+            ("m0", -1.143),
+            ("m1", -0.714),
+        ],
+        initial: DVec3::new(0.7, 0., 0.),
+        dt: 0.002,
+        // The double scroll is flat in y, so it is looked at from above rather than the side
+        framing: framing([-8., 20., 10.], [0., 0., 0.], [-1.6, 1.6], [-0.27, 0.27]),
+        derivative: |params, p| {
+            let [alpha, beta, m0, m1] = unpack(params);
+            // The piecewise linear current through Chua's diode
+            let diode = m1 * p.x + 0.5 * (m0 - m1) * ((p.x + 1.).abs() - (p.x - 1.).abs());
+            DVec3::new(alpha * (p.y - p.x - diode), p.x - p.y + p.z, -beta * p.y)
+        },
+        jacobian: None,
+        equilibria: None,
+    },
+];
+
+/// One of the built-in systems, with its own copy of the parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attractor {
+    spec: &'static Spec,
+    params: Vec<f64>,
+}
+
+impl Attractor {
+    /// The names of every built-in system
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        CATALOGUE.iter().map(|spec| spec.name)
+    }
+
+    /// Every built-in system with its default parameters
+    pub fn all() -> impl Iterator<Item = Attractor> {
+        CATALOGUE.iter().map(Attractor::from_spec)
+    }
+
+    pub fn lorenz() -> Self {
+        Self::from_spec(&CATALOGUE[0])
+    }
+
+    fn from_spec(spec: &'static Spec) -> Self {
+        Self {
+            spec,
+            params: spec.params.iter().map(|&(_, value)| value).collect(),
+        }
+    }
+}
+
+impl Default for Attractor {
+    fn default() -> Self {
+        Self::lorenz()
+    }
+}
+
+impl fmt::Display for Attractor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.spec.name)
+    }
+}
+
+impl FromStr for Attractor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CATALOGUE
+            .iter()
+            .find(|spec| spec.name == s)
+            .map(Attractor::from_spec)
+            .ok_or_else(|| {
+                let names: Vec<_> = Attractor::names().collect();
+                format!(
+                    "unknown system `{s}`, expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl DynamicalSystem for Attractor {
+    fn name(&self) -> &str {
+        self.spec.name
+    }
+
+    fn param_name(&self, index: usize) -> &str {
+        self.spec.params[index].0
+    }
+
+    fn params(&self) -> &[f64] {
+        &self.params
+    }
+
+    fn params_mut(&mut self) -> &mut [f64] {
+        &mut self.params
+    }
+
+    fn derivative(&self, p: DVec3) -> DVec3 {
+        (self.spec.derivative)(&self.params, p)
+    }
+
+    fn jacobian(&self, p: DVec3) -> DMat3 {
+        match self.spec.jacobian {
+            Some(jacobian) => jacobian(&self.params, p),
+            None => numerical_jacobian(|p| self.derivative(p), p),
+        }
+    }
+
+    fn equilibria(&self) -> Vec<DVec3> {
+        match self.spec.equilibria {
+            Some(equilibria) => equilibria(&self.params),
+            None => equilibria::search(
+                |p| self.derivative(p),
+                |p| self.jacobian(p),
+                &self.spec.framing,
+            ),
+        }
+    }
+
+    fn initial_state(&self) -> DVec3 {
+        self.spec.initial
+    }
+
+    fn dt(&self) -> f64 {
+        self.spec.dt
+    }
+
+    fn framing(&self) -> Framing {
+        self.spec.framing
+    }
+
+    fn clone_box(&self) -> Box<dyn DynamicalSystem> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,183 @@
+//! Bifurcation diagrams, what a trajectory settles into as one parameter is swept
+//!
+//! For each value of the parameter a trajectory is started afresh, left to settle onto the
+//! attractor, and then followed while something is recorded about it: the local maxima of
+//! `z`, or where it crosses a Poincaré section. Plotted against the parameter, a fixed point
+//! leaves nothing, a periodic orbit a few dots and chaos a whole smear of them. The values are
+//! independent of each other, so they are simulated in parallel.
+
+use std::{fmt, str::FromStr};
+
+use rayon::prelude::*;
+
+use crate::{maxima, poincare::Section, DynamicalSystem, Simulation};
+
+/// What is recorded about the trajectory at each value of the parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Method {
+    /// The local maxima of `z`
+    #[default]
+    Maxima,
+    /// The first coordinate along the plane of the crossings of a Poincaré section
+    Section,
+}
+
+impl Method {
+    pub const ALL: [Method; 2] = [Method::Maxima, Method::Section];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Method::Maxima => "maxima",
+            Method::Section => "section",
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Method::ALL
+            .into_iter()
+            .find(|method| method.name() == s)
+            .ok_or_else(|| format!("unknown method `{s}`, expected one of: maxima, section"))
+    }
+}
+
+/// A parameter swept over evenly spaced values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sweep {
+    /// The index of the parameter in [`DynamicalSystem::params`]
+    pub param: usize,
+    pub from: f64,
+    pub to: f64,
+    /// How many values are simulated, both ends included
+    pub count: usize,
+    /// How many steps each trajectory takes before anything is recorded
+    pub transient: usize,
+    /// How many steps each trajectory is followed for after the transient
+    pub steps: usize,
+}
+
+/// What was recorded at one value of the parameter
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub param: f64,
+    pub values: Vec<f64>,
+}
+
+impl Sweep {
+    /// The values the parameter takes, in order
+    pub fn params(&self) -> impl Iterator<Item = f64> + '_ {
+        let steps = self.count.saturating_sub(1).max(1) as f64;
+        (0..self.count).map(move |i| self.from + (self.to - self.from) * i as f64 / steps)
+    }
+
+    /// Runs `simulation` again from its current state for every value of the parameter and
+    /// records what `method` asks for, with the section `section` gives for the parameters
+    /// when that is the method
+    ///
+    /// The columns come back in the order of the values, however they were spread over the
+    /// threads. The first error `section` gives is returned instead.
+    pub fn run<F>(
+        &self,
+        simulation: &Simulation,
+        method: Method,
+        section: F,
+    ) -> Result<Vec<Column>, String>
+    where
+        F: Fn(&dyn DynamicalSystem) -> Result<Section, String> + Sync,
+    {
+        let params: Vec<_> = self.params().collect();
+        params
+            .into_par_iter()
+            .map(|param| {
+                let mut simulation = simulation.clone();
+                let mut params = simulation.system.params().to_vec();
+                params[self.param] = param;
+                simulation.set_params(&params);
+                for _ in 0..self.transient {
+                    simulation.step();
+                }
+
+                let values: Vec<f64> = match method {
+                    Method::Maxima => maxima::maxima(&mut simulation, self.steps)
+                        .map(|maximum| maximum.point.z)
+                        .collect(),
+                    Method::Section => {
+                        let section = section(&*simulation.system)?;
+                        section
+                            .crossings(&mut simulation, self.steps)
+                            .map(|crossing| section.plane.coordinates(crossing.point).x)
+                            .collect()
+                    }
+                };
+                simulation
+                    .check()
+                    .map_err(|err| format!("at {param}: {err}"))?;
+                Ok(Column { param, values })
+            })
+            .collect()
+    }
+}
+
+/// The shade of a pixel only a single value fell on
+const FAINTEST: f64 = 170.;
+
+/// Draws `columns` as a `width` by `height` greyscale image, one byte per pixel row by row
+/// from the top, dark where many values fall and white where none do
+///
+/// The parameter runs from `from` on the left to `to` on the right and the values from the
+/// lowest at the bottom to the highest at the top. The shade follows the logarithm of how
+/// many values landed on a pixel, so rarely visited branches still show next to a periodic
+/// orbit that puts every value on the same few pixels.
+pub fn rasterise(
+    columns: &[Column],
+    (from, to): (f64, f64),
+    width: usize,
+    height: usize,
+) -> Vec<u8> {
+    let mut hits = vec![0u32; width * height];
+    let values = || columns.iter().flat_map(|column| &column.values).copied();
+    let low = values().fold(f64::INFINITY, f64::min);
+    let high = values().fold(f64::NEG_INFINITY, f64::max);
+    // Also spreads out a single value, or none at all
+    let (low, high) = if high > low {
+        (low, high)
+    } else {
+        (low - 1., high + 1.)
+    };
+
+    let pixel = |value: f64, from: f64, to: f64, pixels: usize| {
+        let fraction = if to == from {
+            0.5
+        } else {
+            (value - from) / (to - from)
+        };
+        (fraction * (pixels - 1) as f64)
+            .round()
+            .clamp(0., (pixels - 1) as f64) as usize
+    };
+    for column in columns {
+        let x = pixel(column.param, from, to, width);
+        for &value in &column.values {
+            let y = height - 1 - pixel(value, low, high, height);
+            hits[y * width + x] += 1;
+        }
+    }
+
+    let most = (*hits.iter().max().unwrap_or(&0) as f64).ln_1p().max(1.);
+    hits.into_iter()
+        .map(|hits| match hits {
+            0 => 255,
+            // A single value is already a clear grey, next to the white of none
+            hits => (FAINTEST * (1. - (hits as f64).ln_1p() / most)).round() as u8,
+        })
+        .collect()
+}
@@ -0,0 +1,625 @@
+//! Command line options, parsed before the app starts
+//!
+//! Every option overrides the matching value of the scene, if one is given. Without a
+//! subcommand the visualisation is opened, the subcommands run without a window.
+
+use std::path::PathBuf;
+
+use bevy::math::DVec3;
+use clap::{Args, Parser, Subcommand};
+use lorenz_attractor::{
+    bifurcation::Method, export::Format, poincare::Direction, power_spectrum::Window, Attractor,
+    Integrator,
+};
+
+use crate::scene::{check_sequence, Scene, TrajectoryConfig, WING_HUES};
+
+#[derive(Parser, Debug)]
+#[command(about = "A visualisation of the Lorenz attractor")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Also draw a copy of the first trajectory started --epsilon away, and plot how far apart
+    /// the two are over time
+    #[arg(long, conflicts_with = "replay")]
+    pub separation: bool,
+
+    /// Also mark where the trajectories cross the plane of the Poincaré section, drawn
+    /// see-through
+    #[arg(long)]
+    pub poincare: bool,
+
+    /// Also plot every local maximum of z against the one before it, Lorenz's return map
+    #[arg(long)]
+    pub return_map: bool,
+
+    /// Also search for the unstable periodic orbits closing within --max-crossings crossings
+    /// of the Poincaré section, and draw one of them at a time
+    #[arg(long)]
+    pub orbits: bool,
+
+    /// The orbit drawn first, by the wings it goes around like LLR. Implies --orbits
+    #[arg(long, value_parser = sequence)]
+    pub orbit: Option<String>,
+
+    /// Colour each loop by the wing it goes around, from the top of the loop on, instead of by
+    /// x
+    #[arg(long)]
+    pub color_by_wing: bool,
+
+    /// Also mark the equilibria of the system, labelled with the eigenvalues of the Jacobian
+    /// there and what the flow does around them
+    #[arg(long)]
+    pub equilibria: bool,
+
+    /// Also plot the power spectral density of x, y and z along the first trajectory, updated
+    /// as it goes
+    #[arg(long)]
+    pub power_spectrum: bool,
+
+    /// Draw the trajectory recorded in a .npy or .bin file instead of simulating one
+    #[arg(long)]
+    pub replay: Option<PathBuf>,
+
+    /// A TOML scene file to start from
+    #[arg(long, global = true)]
+    pub scene: Option<PathBuf>,
+
+    /// The system to simulate, the scene's parameter values are dropped when it picks a
+    /// different one: lorenz, rossler, chen, lu, aizawa, thomas, halvorsen, dadras, sprott,
+    /// rabinovich-fabrikant, four-wing or chua [default: lorenz]
+    #[arg(long, global = true)]
+    pub system: Option<Attractor>,
+
+    /// An equation like `dx = s * (y - x)`, give one for each of dx, dy and dz to simulate a
+    /// system of your own. Its parameters are set with --param, the scene's are dropped
+    #[arg(long = "equation", global = true, conflicts_with = "system")]
+    pub equations: Vec<String>,
+
+    /// Sets a parameter of the system as `name=value`, can be given several times
+    #[arg(long = "param", global = true, value_parser = param, allow_hyphen_values = true)]
+    pub params: Vec<(String, f64)>,
+
+    /// The σ parameter of the Lorenz system, the Prandtl number [default: 10]
+    #[arg(long, global = true, value_parser = finite)]
+    pub sigma: Option<f64>,
+
+    /// The ρ parameter of the Lorenz system, the Rayleigh number [default: 28]
+    #[arg(long, global = true, value_parser = finite)]
+    pub rho: Option<f64>,
+
+    /// The β parameter of the Lorenz system, a geometric factor [default: 8/3]
+    #[arg(long, global = true, value_parser = finite)]
+    pub beta: Option<f64>,
+
+    /// The time step, or the interval between samples for adaptive integrators
+    /// [default: depends on the system, 0.001 for lorenz]
+    #[arg(long, global = true, value_parser = positive)]
+    pub dt: Option<f64>,
+
+    /// How many steps are simulated every frame [default: 50]
+    #[arg(long, global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    pub steps_per_frame: Option<u32>,
+
+    /// The point a single trajectory starts from as `x,y,z`, replacing those of the scene
+    /// [default: depends on the system, 0.1,0,0.1 for lorenz]
+    #[arg(long, global = true, value_parser = point, allow_hyphen_values = true)]
+    pub initial: Option<DVec3>,
+
+    /// Draw this many trajectories started around the first one's initial state instead
+    /// [default: 0, which draws the scene's trajectories]
+    #[arg(long, global = true)]
+    pub ensemble: Option<usize>,
+
+    /// How far apart the members of the ensemble start, or a trajectory and the copy it is
+    /// compared with to follow their separation or a Lyapunov exponent
+    /// [default: 1e-5 for the ensemble, 1e-8 otherwise]
+    #[arg(long, global = true, value_parser = positive)]
+    pub epsilon: Option<f64>,
+
+    /// The most crossings of the Poincaré section a periodic orbit may take to close
+    /// [default: 6]
+    #[arg(long, global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_crossings: Option<u32>,
+
+    /// The window each segment of the power spectrum is tapered by: rectangular, hann, hamming
+    /// or blackman [default: hann]
+    #[arg(long, global = true)]
+    pub window: Option<Window>,
+
+    /// How many samples each segment the power spectrum is averaged over holds, a power of two
+    /// [default: 4096]
+    #[arg(long, global = true, value_parser = power_of_two)]
+    pub segment: Option<usize>,
+
+    /// The integration scheme: euler, midpoint, rk4 or rk45 [default: rk4]
+    #[arg(long, global = true)]
+    pub integrator: Option<Integrator>,
+
+    /// The normal of the plane of the Poincaré section as `x,y,z` [default: 0,0,1]
+    #[arg(long, global = true, value_parser = point, allow_hyphen_values = true)]
+    pub plane_normal: Option<DVec3>,
+
+    /// Where the plane of the Poincaré section is along its normal, as an expression in the
+    /// parameters [default: rho - 1 for a system with a rho, otherwise the middle of the view]
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    pub plane_offset: Option<String>,
+
+    /// Which way through the plane counts as a crossing: up along the normal, down or both
+    /// [default: down]
+    #[arg(long, global = true)]
+    pub crossing_direction: Option<Direction>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Integrate a trajectory and write its samples as `t,x,y,z`
+    Export(ExportArgs),
+    /// Follow a trajectory and a copy started --epsilon along x from it, and write how far
+    /// apart they are as `t,separation`. The fitted rate of its exponential growth is
+    /// reported on stderr
+    Separation(SeparationArgs),
+    /// Estimate the largest Lyapunov exponent along a trajectory, with a shadow started
+    /// --epsilon away that is pulled back every so often, or all of them with --spectrum
+    Lyapunov(LyapunovArgs),
+    /// Write where a trajectory crosses the plane of the Poincaré section as `t,x,y,z,u,v`,
+    /// with `u` and `v` the coordinates along the plane
+    Poincare(PoincareArgs),
+    /// Write every local maximum of z along a trajectory with the one after it as
+    /// `t,z,next`, the points of Lorenz's return map
+    ReturnMap(ReturnMapArgs),
+    /// Sweep a parameter over a range, and write the local maxima of z or the crossings of
+    /// the Poincaré section a trajectory settles into at each value as `param,value`, the
+    /// points of a bifurcation diagram. The values are simulated in parallel
+    Bifurcation(BifurcationArgs),
+    /// Search for the unstable periodic orbits a trajectory comes close to, and write each
+    /// with the wings it goes around, its period and how unstable it is as
+    /// `sequence,crossings,period,multiplier,exponent,x,y,z`, with a point of it on the plane
+    /// of the Poincaré section
+    Orbits(OrbitsArgs),
+    /// List the equilibria of the system, with the eigenvalues of the Jacobian there and what
+    /// the flow does around them
+    Equilibria,
+    /// Write the wing each loop of a trajectory goes around as `t,x,z,wing`, L or R by the sign
+    /// of x at the local maximum of z the loop ends at, and report the block entropy of the
+    /// sequence and how long it stays on one wing on stderr
+    Symbols(SymbolsArgs),
+    /// Estimate the power spectral density of x, y and z along a trajectory, and write it as
+    /// `frequency,x,y,z`. The frequency with the most power in each is reported on stderr
+    PowerSpectrum(PowerSpectrumArgs),
+    /// Count the pairs of points of a trajectory closer together than each of a range of
+    /// radii, and write the correlation integral as `r,pairs,c`. The correlation dimension
+    /// fitted over its scaling region is reported on stderr
+    Dimension(DimensionArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    /// How many steps to integrate, the initial state is written as well
+    #[arg(long, default_value_t = 10_000)]
+    pub steps: usize,
+
+    /// Which trajectory of the scene to integrate
+    #[arg(long, default_value_t = 0)]
+    pub trajectory: usize,
+
+    /// The output format: csv, jsonl, npy or bin [default: from the output's extension,
+    /// otherwise csv]
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    /// The file to write to instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct SeparationArgs {
+    /// How many steps to integrate, the initial separation is written as well
+    #[arg(long, default_value_t = 40_000)]
+    pub steps: usize,
+
+    /// Which trajectory of the scene to follow
+    #[arg(long, default_value_t = 0)]
+    pub trajectory: usize,
+
+    /// The file to write to instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct LyapunovArgs {
+    /// How many steps to average the growth of the separation over
+    #[arg(long, default_value_t = 1_000_000)]
+    pub steps: usize,
+
+    /// How many steps to take first, for the trajectory to settle onto the attractor
+    #[arg(long, default_value_t = 10_000)]
+    pub transient: usize,
+
+    /// How many steps are taken between pulling the shadow back, or making the tangent
+    /// vectors orthonormal
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    pub interval: u64,
+
+    /// Estimate all three exponents from the variational equations instead, and the
+    /// Kaplan-Yorke dimension they give
+    #[arg(long)]
+    pub spectrum: bool,
+
+    /// Which trajectory of the scene to follow
+    #[arg(long, default_value_t = 0)]
+    pub trajectory: usize,
+}
+
+#[derive(Args, Debug)]
+pub struct PoincareArgs {
+    /// How many steps to integrate after the transient
+    #[arg(long, default_value_t = 1_000_000)]
+    pub steps: usize,
+
+    /// How many steps to take first, for the trajectory to settle onto the attractor
+    #[arg(long, default_value_t = 10_000)]
+    pub transient: usize,
+
+    /// Which trajectory of the scene to follow
+    #[arg(long, default_value_t = 0)]
+    pub trajectory: usize,
+
+    /// The file to write to instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ReturnMapArgs {
+    /// How many steps to integrate after the transient
+    #[arg(long, default_value_t = 1_000_000)]
+    pub steps: usize,
+
+    /// How many steps to take first, for the trajectory to settle onto the attractor
+    #[arg(long, default_value_t = 10_000)]
+    pub transient: usize,
+
+    /// Which trajectory of the scene to follow
+    #[arg(long, default_value_t = 0)]
+    pub trajectory: usize,
+
+    /// The file to write to instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct SymbolsArgs {
+    /// How many steps to integrate after the transient
+    #[arg(long, default_value_t = 2_000_000)]
+    pub steps: usize,
+
+    /// How many steps to take first, for the trajectory to settle onto the attractor
+    #[arg(long, default_value_t = 10_000)]
+    pub transient: usize,
+
+    /// The longest blocks of successive wings the entropy is reported for
+    #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..))]
+    pub blocks: u32,
+
+    /// Which trajectory of the scene to follow
+    #[arg(long, default_value_t = 0)]
+    pub trajectory: usize,
+
+    /// The file to write to instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct PowerSpectrumArgs {
+    /// How many steps to integrate after the transient
+    #[arg(long, default_value_t = 1_000_000)]
+    pub steps: usize,
+
+    /// How many steps to take first, for the trajectory to settle onto the attractor
+    #[arg(long, default_value_t = 10_000)]
+    pub transient: usize,
+
+    /// How many steps apart the samples are taken, or samples apart in a recording
+    /// [default: 10]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub every: Option<u32>,
+
+    /// Take the samples from a trajectory recorded as .npy or .bin instead of simulating one
+    #[arg(long)]
+    pub input: Option<PathBuf>,
+
+    /// Which trajectory of the scene to follow
+    #[arg(long, default_value_t = 0)]
+    pub trajectory: usize,
+
+    /// The file to write to instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct DimensionArgs {
+    /// How many points of the trajectory to count the pairs of
+    #[arg(long, default_value_t = 100_000, value_parser = clap::value_parser!(u32).range(2..))]
+    pub points: u32,
+
+    /// How many steps apart the points are taken, or samples apart in a recording
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+    pub every: u32,
+
+    /// How many steps to take first, for the trajectory to settle onto the attractor
+    #[arg(long, default_value_t = 10_000)]
+    pub transient: usize,
+
+    /// The smallest radius [default: 0.1% of the diagonal of the points' bounding box]
+    #[arg(long, value_parser = positive)]
+    pub from: Option<f64>,
+
+    /// The largest radius [default: 3% of the diagonal of the points' bounding box]
+    #[arg(long, value_parser = positive)]
+    pub to: Option<f64>,
+
+    /// How many radii to count the pairs within, spaced evenly on a log scale
+    #[arg(long, default_value_t = 40, value_parser = clap::value_parser!(u32).range(2..))]
+    pub count: u32,
+
+    /// Pairs of points this close together in the sequence are not counted, as they are close
+    /// in time rather than on the attractor
+    #[arg(long, default_value_t = 100)]
+    pub theiler: usize,
+
+    /// How many successive radii the dimension is fitted over
+    #[arg(long, default_value_t = 12, value_parser = clap::value_parser!(u32).range(2..))]
+    pub width: u32,
+
+    /// Take the points from a trajectory recorded as .npy or .bin instead of simulating one
+    #[arg(long)]
+    pub input: Option<PathBuf>,
+
+    /// Which trajectory of the scene to follow
+    #[arg(long, default_value_t = 0)]
+    pub trajectory: usize,
+
+    /// The file to write to instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct BifurcationArgs {
+    /// The parameter to sweep
+    #[arg(long, default_value = "rho")]
+    pub sweep: String,
+
+    /// The first value of the parameter
+    #[arg(long, default_value_t = 20., value_parser = finite, allow_hyphen_values = true)]
+    pub from: f64,
+
+    /// The last value of the parameter
+    #[arg(long, default_value_t = 250., value_parser = finite, allow_hyphen_values = true)]
+    pub to: f64,
+
+    /// How many evenly spaced values of the parameter to simulate, both ends included
+    #[arg(long, default_value_t = 500, value_parser = clap::value_parser!(u64).range(1..))]
+    pub count: u64,
+
+    /// How many steps to follow the trajectory for at each value, after the transient
+    #[arg(long, default_value_t = 200_000)]
+    pub steps: usize,
+
+    /// How many steps to take first at each value, for the trajectory to settle
+    #[arg(long, default_value_t = 50_000)]
+    pub transient: usize,
+
+    /// What to record: the local maxima of z, or the first coordinate along the plane of the
+    /// crossings of the Poincaré section
+    #[arg(long, default_value_t = Method::default())]
+    pub method: Method,
+
+    /// Which trajectory of the scene to start from at each value
+    #[arg(long, default_value_t = 0)]
+    pub trajectory: usize,
+
+    /// The file to write to instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+
+    /// Also draw the diagram as a PNG image at this path
+    #[arg(long)]
+    pub image: Option<PathBuf>,
+
+    /// The width of the image in pixels
+    #[arg(long, default_value_t = 1200, value_parser = clap::value_parser!(u32).range(1..))]
+    pub width: u32,
+
+    /// The height of the image in pixels
+    #[arg(long, default_value_t = 800, value_parser = clap::value_parser!(u32).range(1..))]
+    pub height: u32,
+}
+
+#[derive(Args, Debug)]
+pub struct OrbitsArgs {
+    /// How many steps to follow the trajectory for after the transient, looking for close
+    /// returns
+    #[arg(long, default_value_t = 1_000_000)]
+    pub steps: usize,
+
+    /// How many steps to take first, for the trajectory to settle onto the attractor
+    #[arg(long, default_value_t = 10_000)]
+    pub transient: usize,
+
+    /// How close a return has to be to be refined into an orbit, as a fraction of how far
+    /// apart the crossings spread over the plane
+    #[arg(long, default_value_t = 0.02, value_parser = positive)]
+    pub closeness: f64,
+
+    /// The most close returns refined for each sequence of wings they went through, the
+    /// closest first
+    #[arg(long, default_value_t = 5)]
+    pub attempts: usize,
+
+    /// Which trajectory of the scene to follow
+    #[arg(long, default_value_t = 0)]
+    pub trajectory: usize,
+
+    /// The file to write to instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+impl Cli {
+    /// Loads the scene, if any, and applies the options on top of it
+    pub fn scene(&self) -> Result<Scene, String> {
+        let mut scene = match &self.scene {
+            Some(path) => Scene::load(path).map_err(|err| format!("{}: {err}", path.display()))?,
+            None => Scene::default(),
+        };
+
+        let system = &mut scene.system;
+        if let Some(name) = &self.system {
+            if system.name.as_ref() != Some(name) || system.equations.is_some() {
+                system.params.clear();
+            }
+            system.name = Some(name.clone());
+            system.equations = None;
+        }
+        if !self.equations.is_empty() {
+            system.params.clear();
+            system.name = None;
+            system.equations = Some(self.equations.clone());
+        }
+
+        let shorthands = [
+            ("sigma", self.sigma),
+            ("rho", self.rho),
+            ("beta", self.beta),
+        ];
+        let shorthands = shorthands
+            .into_iter()
+            .filter_map(|(name, value)| Some((name.to_owned(), value?)));
+        system
+            .params
+            .extend(shorthands.chain(self.params.iter().cloned()));
+        system.build()?;
+
+        let integration = &mut scene.integration;
+        integration.dt = self.dt.or(integration.dt);
+        integration.steps_per_frame = self.steps_per_frame.unwrap_or(integration.steps_per_frame);
+        integration.integrator = self.integrator.unwrap_or(integration.integrator);
+
+        let ensemble = &mut scene.ensemble;
+        ensemble.size = self.ensemble.unwrap_or(ensemble.size);
+        ensemble.epsilon = self.epsilon.unwrap_or(ensemble.epsilon);
+
+        let separation = &mut scene.separation;
+        separation.show |= self.separation;
+        separation.epsilon = self.epsilon.unwrap_or(separation.epsilon);
+        if separation.show && scene.ensemble.size > 0 {
+            return Err("the separation cannot be shown along with an ensemble".into());
+        }
+
+        let poincare = &mut scene.poincare;
+        poincare.show |= self.poincare;
+        if let Some(normal) = self.plane_normal {
+            if normal == DVec3::ZERO {
+                return Err("--plane-normal must not be zero".into());
+            }
+            poincare.normal = normal.into();
+        }
+        poincare.offset = self.plane_offset.clone().or(poincare.offset.take());
+        poincare.direction = self.crossing_direction.unwrap_or(poincare.direction);
+        poincare
+            .section(&*system.build()?)
+            .map_err(|err| format!("plane {err}"))?;
+
+        scene.return_map.show |= self.return_map;
+        scene.equilibria.show |= self.equilibria;
+        if self.color_by_wing {
+            let color_map = &mut scene.color_map;
+            color_map.wings = color_map.wings.or(Some(WING_HUES));
+        }
+
+        let orbits = &mut scene.orbits;
+        orbits.show |= self.orbits || self.orbit.is_some();
+        orbits.max_crossings = self
+            .max_crossings
+            .map_or(orbits.max_crossings, |max| max as usize);
+        orbits.selected = self.orbit.clone().or(orbits.selected.take());
+
+        let power_spectrum = &mut scene.power_spectrum;
+        power_spectrum.show |= self.power_spectrum;
+        power_spectrum.window = self.window.unwrap_or(power_spectrum.window);
+        power_spectrum.segment = self.segment.unwrap_or(power_spectrum.segment);
+
+        if let Some(initial) = self.initial {
+            scene.trajectories = vec![TrajectoryConfig {
+                initial: Some(initial.into()),
+                ..Default::default()
+            }];
+        }
+
+        Ok(scene)
+    }
+}
+
+fn finite(s: &str) -> Result<f64, String> {
+    let value: f64 = s.parse().map_err(|_| format!("`{s}` is not a number"))?;
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(format!("`{s}` is not a finite number"))
+    }
+}
+
+fn positive(s: &str) -> Result<f64, String> {
+    let value = finite(s)?;
+    if value > 0. {
+        Ok(value)
+    } else {
+        Err(format!("`{s}` must be greater than zero"))
+    }
+}
+
+fn param(s: &str) -> Result<(String, f64), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected `name=value`, got `{s}`"))?;
+    Ok((name.trim().to_owned(), finite(value.trim())?))
+}
+
+fn power_of_two(s: &str) -> Result<usize, String> {
+    let value: usize = s
+        .parse()
+        .map_err(|_| format!("`{s}` is not a whole number"))?;
+    if value >= 2 && value.is_power_of_two() {
+        Ok(value)
+    } else {
+        Err(format!("`{s}` is not a power of two, at least 2"))
+    }
+}
+
+fn sequence(s: &str) -> Result<String, String> {
+    check_sequence(s)?;
+    Ok(s.to_owned())
+}
+
+fn point(s: &str) -> Result<DVec3, String> {
+    let coordinates = s
+        .split(',')
+        .map(|coordinate| finite(coordinate.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match coordinates[..] {
+        [x, y, z] => Ok(DVec3::new(x, y, z)),
+        _ => Err(format!(
+            "expected three comma separated coordinates, got {}",
+            coordinates.len()
+        )),
+    }
+}
@@ -0,0 +1,229 @@
+//! The correlation dimension of an attractor, after Grassberger and Procaccia
+//!
+//! The correlation integral `C(r)` is the fraction of pairs of points on the attractor closer
+//! together than `r`. For small `r` it goes as `r^D`, and the exponent `D`, the slope of
+//! `log C` against `log r`, is the correlation dimension: 2.05 for the Lorenz attractor,
+//! a surface folded into itself over and over. The slope only holds over a scaling region,
+//! below it too few pairs are left to count and above it `C` saturates at the size of the
+//! attractor.
+//!
+//! Points that follow each other closely in time are close for reasons that have nothing to
+//! do with the attractor, so pairs fewer than a Theiler window of samples apart are left out.
+//! The pairs are found with a k-d tree, which only looks at the points near each one.
+
+use glam::DVec3;
+use rayon::prelude::*;
+
+/// How few points a node of the tree holds before they are just checked one by one
+const LEAF: usize = 16;
+
+/// How few pairs a radius needs before its count is trusted for the fit
+const MIN_PAIRS: u64 = 1000;
+
+/// A k-d tree of points, for finding those within a distance of any other quickly
+///
+/// The points are kept in a single array, ordered so that each node is a slice split at its
+/// middle point: the points before it are no further along the node's axis and those after
+/// it no nearer. The axis goes round x, y and z from the root down.
+#[derive(Debug, Clone)]
+pub struct KdTree {
+    /// Each point with its index in the points the tree was built from
+    points: Vec<(usize, DVec3)>,
+}
+
+impl KdTree {
+    pub fn new(points: &[DVec3]) -> Self {
+        let mut points: Vec<_> = points.iter().copied().enumerate().collect();
+        build(&mut points, 0);
+        Self { points }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Calls `visit` with the index and distance of every point closer to `center` than
+    /// `radius`
+    pub fn within(&self, center: DVec3, radius: f64, mut visit: impl FnMut(usize, f64)) {
+        search(&self.points, 0, center, radius, &mut visit);
+    }
+}
+
+fn build(points: &mut [(usize, DVec3)], axis: usize) {
+    if points.len() <= LEAF {
+        return;
+    }
+    let middle = points.len() / 2;
+    points.select_nth_unstable_by(middle, |a, b| a.1[axis].total_cmp(&b.1[axis]));
+    let (before, after) = points.split_at_mut(middle);
+    build(before, (axis + 1) % 3);
+    build(&mut after[1..], (axis + 1) % 3);
+}
+
+fn search(
+    points: &[(usize, DVec3)],
+    axis: usize,
+    center: DVec3,
+    radius: f64,
+    visit: &mut impl FnMut(usize, f64),
+) {
+    let mut check = |&(index, p): &(usize, DVec3)| {
+        let squared = p.distance_squared(center);
+        if squared < radius * radius {
+            visit(index, squared.sqrt());
+        }
+    };
+    if points.len() <= LEAF {
+        points.iter().for_each(check);
+        return;
+    }
+
+    let middle = points.len() / 2;
+    check(&points[middle]);
+    let along = center[axis] - points[middle].1[axis];
+    if along < radius {
+        search(&points[..middle], (axis + 1) % 3, center, radius, visit);
+    }
+    if along > -radius {
+        search(&points[middle + 1..], (axis + 1) % 3, center, radius, visit);
+    }
+}
+
+/// `count` radii spaced evenly on a log scale from `from` to `to`, both included
+pub fn radii(from: f64, to: f64, count: usize) -> Vec<f64> {
+    let ratio = (to / from).ln();
+    (0..count)
+        .map(|i| from * (ratio * i as f64 / (count.max(2) - 1) as f64).exp())
+        .collect()
+}
+
+/// The correlation integral of a set of points at a range of radii
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrelationIntegral {
+    /// In increasing order
+    pub radii: Vec<f64>,
+    /// How many pairs are closer together than each radius
+    pub pairs: Vec<u64>,
+    /// How many pairs there are in all, outside the Theiler window
+    pub total: u64,
+}
+
+impl CorrelationIntegral {
+    /// Counts the pairs of `points` closer than each of `radii`, given in increasing order,
+    /// leaving out those no more than `theiler` samples apart. The points are gone through in
+    /// parallel
+    pub fn new(points: &[DVec3], radii: Vec<f64>, theiler: usize) -> Self {
+        let largest = radii.last().copied().unwrap_or(0.);
+        let tree = KdTree::new(points);
+
+        // Each pair is counted once, from the earlier of its two points
+        let counts = points
+            .par_iter()
+            .enumerate()
+            .fold(
+                || vec![0; radii.len()],
+                |mut counts: Vec<u64>, (i, &p)| {
+                    tree.within(p, largest, |j, distance| {
+                        if j > i + theiler {
+                            let bin = radii.partition_point(|&r| r <= distance);
+                            counts[bin] += 1;
+                        }
+                    });
+                    counts
+                },
+            )
+            .reduce(
+                || vec![0; radii.len()],
+                |a, b| a.iter().zip(b).map(|(a, b)| a + b).collect(),
+            );
+        let pairs = counts
+            .iter()
+            .scan(0, |sum, count| {
+                *sum += count;
+                Some(*sum)
+            })
+            .collect();
+
+        let n = points.len() as u64;
+        let total = (0..n)
+            .map(|i| n.saturating_sub(i + theiler as u64 + 1))
+            .sum();
+        Self {
+            radii,
+            pairs,
+            total,
+        }
+    }
+
+    /// Each radius with the fraction of pairs closer together than it, `C(r)`
+    pub fn values(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        let total = self.total.max(1) as f64;
+        self.radii
+            .iter()
+            .zip(&self.pairs)
+            .map(move |(&r, &pairs)| (r, pairs as f64 / total))
+    }
+
+    /// Fits the correlation dimension over the straightest run of `width` successive radii
+    /// on a log-log scale, among those with enough pairs to count on
+    ///
+    /// The straightest run is the one a least squares line misses by the least, which keeps
+    /// clear of both the noise at small radii and the bend where `C` saturates. There is
+    /// nothing to fit without `width` such radii, or fewer than two.
+    pub fn fit(&self, width: usize) -> Option<ScalingFit> {
+        let logs: Vec<(f64, f64)> = self
+            .values()
+            .zip(&self.pairs)
+            .filter(|&(_, &pairs)| pairs >= MIN_PAIRS)
+            .map(|((r, c), _)| (r.ln(), c.ln()))
+            .collect();
+        if width < 2 {
+            return None;
+        }
+
+        logs.windows(width)
+            .map(|window| {
+                let (slope, intercept) = least_squares(window);
+                let residual: f64 = window
+                    .iter()
+                    .map(|&(x, y)| (y - slope * x - intercept).powi(2))
+                    .sum();
+                let fit = ScalingFit {
+                    dimension: slope,
+                    from: window[0].0.exp(),
+                    to: window[width - 1].0.exp(),
+                };
+                (residual, fit)
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, fit)| fit)
+    }
+}
+
+/// The correlation dimension fitted over a range of radii
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScalingFit {
+    pub dimension: f64,
+    /// The smallest and largest radii of the scaling region
+    pub from: f64,
+    pub to: f64,
+}
+
+/// The slope and intercept of the least squares line through `points`
+fn least_squares(points: &[(f64, f64)]) -> (f64, f64) {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|&(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|&(_, y)| y).sum::<f64>() / n;
+    let (covariance, variance) = points.iter().fold((0., 0.), |(cov, var), &(x, y)| {
+        (
+            cov + (x - mean_x) * (y - mean_y),
+            var + (x - mean_x) * (x - mean_x),
+        )
+    });
+    let slope = covariance / variance;
+    (slope, mean_y - slope * mean_x)
+}
@@ -0,0 +1,102 @@
+//! How quickly two trajectories that start a tiny distance apart move away from each other
+//!
+//! While the distance is small compared to the attractor it grows roughly exponentially, at a
+//! rate close to the largest Lyapunov exponent, until it saturates at the size of the attractor.
+
+use glam::DVec3;
+
+use crate::Simulation;
+
+/// A trajectory followed side by side with a copy of it that starts slightly off
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub reference: Simulation,
+    pub perturbed: Simulation,
+}
+
+impl Divergence {
+    /// Follows `simulation` and a copy moved by `offset`
+    pub fn new(simulation: Simulation, offset: DVec3) -> Self {
+        let mut perturbed = simulation.clone();
+        perturbed.state += offset;
+        perturbed.adaptive.reset(perturbed.time, perturbed.state);
+
+        Self {
+            reference: simulation,
+            perturbed,
+        }
+    }
+
+    /// Fails with the reason either integration stopped, if one has
+    pub fn check(&self) -> Result<(), String> {
+        self.reference.check()?;
+        self.perturbed.check()
+    }
+
+    /// The distance between the two states
+    pub fn separation(&self) -> f64 {
+        self.reference.state.distance(self.perturbed.state)
+    }
+
+    /// The current time and separation, followed by those after each of the next `steps` steps
+    pub fn samples(&mut self, steps: usize) -> impl Iterator<Item = (f64, f64)> + '_ {
+        std::iter::once((self.reference.time, self.separation())).chain((0..steps).map(move |_| {
+            self.reference.step();
+            self.perturbed.step();
+            (self.reference.time, self.separation())
+        }))
+    }
+}
+
+/// An exponential `initial * exp(rate * t)` fitted to a growing separation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExponentialFit {
+    pub rate: f64,
+    pub initial: f64,
+    /// The time of the last sample the fit covers
+    pub until: f64,
+}
+
+impl ExponentialFit {
+    pub fn evaluate(&self, t: f64) -> f64 {
+        self.initial * (self.rate * t).exp()
+    }
+}
+
+/// Fits an exponential to the `(t, separation)` samples while they are still growing
+///
+/// The fit is a least squares line through the logarithm of the separation, over the samples
+/// before it first reaches a hundredth of its largest value, beyond which it starts to
+/// saturate. There is nothing to fit without at least two such samples.
+pub fn fit_growth(samples: &[(f64, f64)]) -> Option<ExponentialFit> {
+    let largest = samples.iter().map(|&(_, d)| d).fold(0., f64::max);
+    let growing: Vec<(f64, f64)> = samples
+        .iter()
+        .take_while(|&&(_, d)| d < 0.01 * largest)
+        .filter(|&&(_, d)| d > 0.)
+        .map(|&(t, d)| (t, d.ln()))
+        .collect();
+    if growing.len() < 2 {
+        return None;
+    }
+
+    let n = growing.len() as f64;
+    let mean_t = growing.iter().map(|&(t, _)| t).sum::<f64>() / n;
+    let mean_ln = growing.iter().map(|&(_, ln)| ln).sum::<f64>() / n;
+    let (covariance, variance) = growing.iter().fold((0., 0.), |(cov, var), &(t, ln)| {
+        (
+            cov + (t - mean_t) * (ln - mean_ln),
+            var + (t - mean_t) * (t - mean_t),
+        )
+    });
+    if variance == 0. {
+        return None;
+    }
+
+    let rate = covariance / variance;
+    Some(ExponentialFit {
+        rate,
+        initial: (mean_ln - rate * mean_t).exp(),
+        until: growing[growing.len() - 1].0,
+    })
+}
@@ -0,0 +1,113 @@
+//! Many trajectories started a tiny distance apart, each drawn in a colour of its own
+//!
+//! `[` and `]` shrink and grow the distance tenfold and start every member again.
+
+use bevy::{math::DVec3, prelude::*};
+use lorenz_attractor::perturbation::perturbations;
+
+use crate::{
+    restart,
+    scene::{ColorMap, Scene},
+    spawn_simulated, LineMaterial, Simulated, Trajectory,
+};
+
+pub struct EnsemblePlugin;
+
+impl Plugin for EnsemblePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_ensemble).add_systems(
+            Update,
+            (
+                edit_epsilon,
+                respread_ensemble.run_if(resource_exists_and_changed::<Ensemble>()),
+            )
+                .chain()
+                .before(crate::simulate),
+        );
+    }
+}
+
+/// Trajectories starting at `epsilon` from `center`, drawn in place of the scene's
+#[derive(Resource)]
+pub struct Ensemble {
+    pub size: usize,
+    pub epsilon: f64,
+    pub center: DVec3,
+}
+
+impl Ensemble {
+    /// The starting point of each member
+    fn initial_states(&self) -> impl Iterator<Item = DVec3> + '_ {
+        perturbations(self.size, self.epsilon).map(|offset| self.center + offset)
+    }
+}
+
+/// Which member of the ensemble a trajectory is
+#[derive(Component)]
+struct Member(usize);
+
+fn spawn_ensemble(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    ensemble: Option<Res<Ensemble>>,
+    scene: Res<Scene>,
+    color_map: Res<ColorMap>,
+) {
+    let Some(ensemble) = ensemble else {
+        return;
+    };
+
+    let material = materials.add(LineMaterial {
+        color: Color::WHITE,
+    });
+    let simulation = scene.simulation(&scene.trajectories[0]);
+
+    for (index, initial) in ensemble.initial_states().enumerate() {
+        let mut simulation = simulation.clone();
+        simulation.restart(initial);
+
+        // Hues evenly spaced around the colour wheel
+        let hue = 360. * index as f32 / ensemble.size as f32;
+        let simulated = Simulated {
+            simulation,
+            initial,
+            color: Some(Color::hsl(hue, 0.9, 0.5)),
+            wing: None,
+        };
+
+        let entity = spawn_simulated(&mut commands, simulated, material.clone(), &color_map);
+        commands.entity(entity).insert(Member(index));
+    }
+}
+
+fn edit_epsilon(keys: Res<Input<KeyCode>>, ensemble: Option<ResMut<Ensemble>>) {
+    let Some(mut ensemble) = ensemble else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::BracketLeft) {
+        ensemble.epsilon /= 10.;
+    }
+    if keys.just_pressed(KeyCode::BracketRight) {
+        ensemble.epsilon *= 10.;
+    }
+}
+
+/// Starts every member again at the new distance from the center
+fn respread_ensemble(
+    ensemble: Res<Ensemble>,
+    color_map: Res<ColorMap>,
+    mut members: Query<(&Member, &mut Simulated, &mut Trajectory)>,
+) {
+    // Nothing needs to move when the ensemble has only just been spawned
+    if ensemble.is_added() {
+        return;
+    }
+    info!("ensemble epsilon {:e}", ensemble.epsilon);
+
+    let initial_states: Vec<_> = ensemble.initial_states().collect();
+    for (member, mut simulated, mut trajectory) in &mut members {
+        simulated.initial = initial_states[member.0];
+        restart(&mut simulated, &mut trajectory, &color_map);
+    }
+}
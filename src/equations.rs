@@ -0,0 +1,217 @@
+//! Systems of equations typed in at runtime
+//!
+//! Each equation gives the derivative of one variable as an [`Expr`], for example:
+//!
+//! ```text
+//! dx = s * (y - x)
+//! dy = x * (r - z) - y
+//! dz = x * y - b * z
+//! ```
+//!
+//! Every other name in them has to be one of the parameters the system is given.
+
+use std::fmt;
+
+use glam::DVec3;
+
+use crate::{
+    expr::{Expr, ParseError},
+    system::{DynamicalSystem, Framing},
+    Integrator,
+};
+
+/// The names on the left hand side of each equation, in the order of the state's variables
+const DERIVATIVES: [&str; 3] = ["dx", "dy", "dz"];
+
+/// Names the parameters cannot take as the expressions already give them a meaning
+const RESERVED: [&str; 5] = ["x", "y", "z", "pi", "e"];
+
+/// What is wrong with a system of equations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EquationError {
+    /// A mistake within one of the equations
+    Syntax { equation: String, error: ParseError },
+    /// There is no equation for `dx`, `dy` or `dz`
+    Missing(&'static str),
+    /// A parameter has a name the expressions use for something else
+    Reserved(String),
+    /// A parameter is given a value but none of the equations use it
+    Unused(String),
+}
+
+impl fmt::Display for EquationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EquationError::Syntax { equation, error } => {
+                // Point out the spot below the equation
+                writeln!(f, "{error}")?;
+                writeln!(f, "  {equation}")?;
+                write!(f, "  {:>width$}", '^', width = error.position + 1)
+            }
+            EquationError::Missing(derivative) => {
+                write!(f, "there is no equation for `{derivative}`")
+            }
+            EquationError::Reserved(name) => {
+                write!(f, "a parameter cannot be called `{name}`")
+            }
+            EquationError::Unused(name) => {
+                write!(
+                    f,
+                    "the parameter `{name}` is not used by any of the equations"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for EquationError {}
+
+/// A system made of equations typed in at runtime
+#[derive(Debug, Clone)]
+pub struct Equations {
+    names: Vec<String>,
+    params: Vec<f64>,
+    /// The expressions for `dx`, `dy` and `dz`
+    derivatives: [Expr; 3],
+    framing: Framing,
+}
+
+impl Equations {
+    /// The state every system of equations starts from, off the origin where many have a fixed
+    /// point
+    pub const INITIAL_STATE: DVec3 = DVec3::new(0.1, 0.1, 0.1);
+
+    /// Parses one equation like `dx = …` for each variable, in any order, which may use the
+    /// given parameters
+    pub fn new(
+        equations: &[impl AsRef<str>],
+        params: &[(String, f64)],
+    ) -> Result<Self, EquationError> {
+        let names: Vec<&str> = params.iter().map(|(name, _)| name.as_str()).collect();
+        if let Some(name) = names.iter().find(|name| RESERVED.contains(name)) {
+            return Err(EquationError::Reserved(name.to_string()));
+        }
+
+        let mut derivatives = [None, None, None];
+        for equation in equations {
+            let equation = equation.as_ref();
+            let syntax = |error| EquationError::Syntax {
+                equation: equation.to_owned(),
+                error,
+            };
+
+            let Some((left, right)) = equation.split_once('=') else {
+                return Err(syntax(ParseError {
+                    position: equation.chars().count(),
+                    message: "expected an equation like `dx = …`, found no `=`".into(),
+                }));
+            };
+
+            let name = left.trim();
+            let name_position = left.chars().take_while(|c| c.is_whitespace()).count();
+            let Some(index) = DERIVATIVES
+                .iter()
+                .position(|derivative| *derivative == name)
+            else {
+                return Err(syntax(ParseError {
+                    position: name_position,
+                    message: format!("expected `dx`, `dy` or `dz` before `=`, found `{name}`"),
+                }));
+            };
+            if derivatives[index].is_some() {
+                return Err(syntax(ParseError {
+                    position: name_position,
+                    message: format!("there is more than one equation for `{name}`"),
+                }));
+            }
+
+            // Positions within the right hand side are moved along to count from the start
+            let offset = left.chars().count() + 1;
+            let expr = Expr::parse(right, &names).map_err(|error| {
+                syntax(ParseError {
+                    position: error.position + offset,
+                    ..error
+                })
+            })?;
+            derivatives[index] = Some(expr);
+        }
+
+        let [Some(dx), Some(dy), Some(dz)] = derivatives else {
+            let missing = derivatives.iter().position(Option::is_none).unwrap();
+            return Err(EquationError::Missing(DERIVATIVES[missing]));
+        };
+        let derivatives = [dx, dy, dz];
+
+        if let Some(index) =
+            (0..names.len()).find(|&index| !derivatives.iter().any(|expr| expr.uses_param(index)))
+        {
+            return Err(EquationError::Unused(names[index].to_owned()));
+        }
+
+        let mut equations = Self {
+            names: names.into_iter().map(str::to_owned).collect(),
+            params: params.iter().map(|&(_, value)| value).collect(),
+            derivatives,
+            framing: Framing::fit([Self::INITIAL_STATE]).unwrap(),
+        };
+        equations.framing = equations.fit_framing();
+        Ok(equations)
+    }
+
+    /// Frames what the flow settles into after a while, as far as it stays finite
+    fn fit_framing(&self) -> Framing {
+        let mut p = Self::INITIAL_STATE;
+        let mut points = Vec::new();
+        for step in 0..20_000 {
+            p = Integrator::RungeKutta4.step(|p| self.derivative(p), p, self.dt());
+            if !p.is_finite() {
+                break;
+            }
+            // Leave out the approach from the initial state
+            if step >= 5_000 {
+                points.push(p);
+            }
+        }
+
+        Framing::fit(points).unwrap_or(self.framing)
+    }
+}
+
+impl DynamicalSystem for Equations {
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    fn param_name(&self, index: usize) -> &str {
+        &self.names[index]
+    }
+
+    fn params(&self) -> &[f64] {
+        &self.params
+    }
+
+    fn params_mut(&mut self) -> &mut [f64] {
+        &mut self.params
+    }
+
+    fn derivative(&self, p: DVec3) -> DVec3 {
+        let [dx, dy, dz] = &self.derivatives;
+        DVec3::new(
+            dx.evaluate(p, &self.params),
+            dy.evaluate(p, &self.params),
+            dz.evaluate(p, &self.params),
+        )
+    }
+
+    fn initial_state(&self) -> DVec3 {
+        Self::INITIAL_STATE
+    }
+
+    fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    fn clone_box(&self) -> Box<dyn DynamicalSystem> {
+        Box::new(self.clone())
+    }
+}
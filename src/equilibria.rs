@@ -0,0 +1,253 @@
+//! The equilibria of a system, the points where the flow stands still, and what the flow does
+//! close to them
+//!
+//! Near an equilibrium the flow behaves like its linearisation, the Jacobian there. Its
+//! eigenvalues tell whether trajectories are drawn in or pushed away along each direction,
+//! and whether they wind around it while they are: the Lorenz attractor is woven between a
+//! saddle at the origin and two spiral saddles at the centres of its wings.
+
+use std::fmt;
+
+use glam::{DMat3, DVec3};
+
+use crate::{system::Framing, DynamicalSystem};
+
+/// How many Newton iterations a search is given to converge from each starting point
+const ITERATIONS: usize = 100;
+
+/// How many starting points the search spreads along each axis of the view
+const STARTS: usize = 6;
+
+/// An eigenvalue of the Jacobian, real unless `im` is not zero
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Eigenvalue {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl fmt::Display for Eigenvalue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(3);
+        if self.im == 0. {
+            write!(f, "{:.*}", precision, self.re)
+        } else {
+            write!(
+                f,
+                "{:.*} ± {:.*}i",
+                precision,
+                self.re,
+                precision,
+                self.im.abs()
+            )
+        }
+    }
+}
+
+/// What the flow does around an equilibrium, going by the eigenvalues of its Jacobian
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Straight in or straight out along every direction
+    Node { stable: bool },
+    /// Winding around it on the way in or out
+    Spiral { stable: bool },
+    /// In along some directions and out along others, winding around it when `spiral`
+    Saddle { spiral: bool },
+    /// An eigenvalue on the imaginary axis, where the linearisation does not decide
+    NonHyperbolic,
+}
+
+impl Kind {
+    /// Classifies the eigenvalues of a Jacobian
+    pub fn of(eigenvalues: &[Eigenvalue; 3]) -> Kind {
+        let size = eigenvalues
+            .iter()
+            .map(|eigenvalue| eigenvalue.re.hypot(eigenvalue.im))
+            .fold(1., f64::max);
+        if eigenvalues
+            .iter()
+            .any(|eigenvalue| eigenvalue.re.abs() <= 1e-9 * size)
+        {
+            return Kind::NonHyperbolic;
+        }
+
+        let spiral = eigenvalues.iter().any(|eigenvalue| eigenvalue.im != 0.);
+        let unstable = eigenvalues
+            .iter()
+            .filter(|eigenvalue| eigenvalue.re > 0.)
+            .count();
+        match (unstable, spiral) {
+            (1 | 2, spiral) => Kind::Saddle { spiral },
+            (unstable, false) => Kind::Node {
+                stable: unstable == 0,
+            },
+            (unstable, true) => Kind::Spiral {
+                stable: unstable == 0,
+            },
+        }
+    }
+
+    /// Whether every nearby trajectory is drawn in
+    pub fn is_stable(self) -> bool {
+        matches!(
+            self,
+            Kind::Node { stable: true } | Kind::Spiral { stable: true }
+        )
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stability = |stable| if stable { "stable" } else { "unstable" };
+        match *self {
+            Kind::Node { stable } => write!(f, "{} node", stability(stable)),
+            Kind::Spiral { stable } => write!(f, "{} spiral", stability(stable)),
+            Kind::Saddle { spiral: false } => f.write_str("saddle"),
+            Kind::Saddle { spiral: true } => f.write_str("spiral saddle"),
+            Kind::NonHyperbolic => f.write_str("non-hyperbolic"),
+        }
+    }
+}
+
+/// A point where the flow stands still
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Equilibrium {
+    pub point: DVec3,
+    /// The eigenvalues of the Jacobian at `point`, the largest real part first and a complex
+    /// pair with the positive imaginary part first
+    pub eigenvalues: [Eigenvalue; 3],
+    pub kind: Kind,
+}
+
+impl Equilibrium {
+    /// Linearises `system` around the equilibrium at `point`
+    pub fn new(system: &dyn DynamicalSystem, point: DVec3) -> Self {
+        let eigenvalues = eigenvalues(system.jacobian(point));
+        Self {
+            point,
+            eigenvalues,
+            kind: Kind::of(&eigenvalues),
+        }
+    }
+
+    /// The eigenvalues with each complex pair given once, by the one written `a ± bi`
+    pub fn distinct_eigenvalues(&self) -> impl Iterator<Item = Eigenvalue> + '_ {
+        self.eigenvalues
+            .iter()
+            .copied()
+            .filter(|eigenvalue| eigenvalue.im >= 0.)
+    }
+}
+
+/// Every equilibrium of `system` it knows of or could find, with what the flow does there
+pub fn equilibria(system: &dyn DynamicalSystem) -> Vec<Equilibrium> {
+    system
+        .equilibria()
+        .into_iter()
+        .map(|point| Equilibrium::new(system, point))
+        .collect()
+}
+
+/// The equilibria Newton's method converges to from a grid of starting points spread over
+/// and around the view of `framing`, and from the origin, in the order they were found
+///
+/// Equilibria far from the attractor, or with basins of attraction the grid misses, can be
+/// missed in turn.
+pub fn search(
+    derivative: impl Fn(DVec3) -> DVec3,
+    jacobian: impl Fn(DVec3) -> DMat3,
+    framing: &Framing,
+) -> Vec<DVec3> {
+    let [x0, x1] = framing.x;
+    let [y0, y1] = framing.y;
+    // The ranges of the framing cover the middle of the attractor, spread a little wider
+    let half = DVec3::new(x1 - x0, y1 - y0, (x1 - x0).max(y1 - y0));
+    let center = DVec3::new((x0 + x1) / 2., (y0 + y1) / 2., framing.look_at.z);
+    let scale = half.max_element().max(1.);
+    let grid = (0..STARTS.pow(3)).map(|index| {
+        let fraction = |i: usize| 2. * (i % STARTS) as f64 / (STARTS - 1) as f64 - 1.;
+        let along = DVec3::new(
+            fraction(index),
+            fraction(index / STARTS),
+            fraction(index / STARTS / STARTS),
+        );
+        center + along * half
+    });
+
+    let mut found: Vec<DVec3> = Vec::new();
+    for start in std::iter::once(DVec3::ZERO).chain(grid) {
+        let Some(point) = newton(&derivative, &jacobian, start, scale) else {
+            continue;
+        };
+        if found
+            .iter()
+            .all(|other| other.distance(point) > 1e-6 * scale)
+        {
+            found.push(point);
+        }
+    }
+    found
+}
+
+/// Where Newton's method converges to from `start`, if it does before wandering off more than
+/// a hundred times `scale` away
+fn newton(
+    derivative: impl Fn(DVec3) -> DVec3,
+    jacobian: impl Fn(DVec3) -> DMat3,
+    start: DVec3,
+    scale: f64,
+) -> Option<DVec3> {
+    let mut p = start;
+    for _ in 0..ITERATIONS {
+        let jacobian = jacobian(p);
+        if jacobian.determinant() == 0. {
+            return None;
+        }
+        let step = jacobian.inverse() * derivative(p);
+        p -= step;
+        if !p.is_finite() || p.length() > 100. * scale {
+            return None;
+        }
+        if step.length() <= 1e-12 * p.length().max(1.) {
+            return Some(p);
+        }
+    }
+    None
+}
+
+/// The eigenvalues of `m`, the roots of its characteristic polynomial
+///
+/// The cubic is solved in closed form: one root is always real, and the other two are either
+/// real as well or a complex pair. They are sorted by real part, largest first.
+pub fn eigenvalues(m: DMat3) -> [Eigenvalue; 3] {
+    // λ³ + aλ² + bλ + c, with the sum of the principal minors of order two as b
+    let a = -(m.x_axis.x + m.y_axis.y + m.z_axis.z);
+    let minor = |i: usize, j: usize| m.col(i)[i] * m.col(j)[j] - m.col(j)[i] * m.col(i)[j];
+    let b = minor(0, 1) + minor(0, 2) + minor(1, 2);
+    let c = -m.determinant();
+
+    // Substituting λ = t - a/3 leaves t³ + pt + q
+    let shift = -a / 3.;
+    let p = b - a * a / 3.;
+    let q = 2. * a * a * a / 27. - a * b / 3. + c;
+    let discriminant = (q / 2.).powi(2) + (p / 3.).powi(3);
+    let real = |re| Eigenvalue { re, im: 0. };
+
+    let mut eigenvalues = if discriminant > 0. {
+        let root = discriminant.sqrt();
+        let (u, v) = ((-q / 2. + root).cbrt(), (-q / 2. - root).cbrt());
+        let pair = |im| Eigenvalue {
+            re: shift - (u + v) / 2.,
+            im,
+        };
+        let im = 3f64.sqrt() / 2. * (u - v).abs();
+        [real(shift + u + v), pair(im), pair(-im)]
+    } else if p == 0. {
+        [real(shift); 3]
+    } else {
+        let r = 2. * (-p / 3.).sqrt();
+        let angle = ((3. * q / (p * r)).clamp(-1., 1.)).acos() / 3.;
+        [0., 1., 2.].map(|k| real(shift + r * (angle - 2. * std::f64::consts::PI * k / 3.).cos()))
+    };
+    eigenvalues.sort_by(|a, b| b.re.total_cmp(&a.re).then(b.im.total_cmp(&a.im)));
+    eigenvalues
+}
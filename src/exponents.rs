@@ -0,0 +1,125 @@
+//! Running estimates of the Lyapunov exponents of the system
+//!
+//! L starts and stops estimating them along a trajectory of its own, which is not drawn: the
+//! largest with a shadow trajectory, and all three with the Kaplan-Yorke dimension they give
+//! from the variational equations. The estimates start again whenever a parameter changes.
+
+use bevy::prelude::*;
+use lorenz_attractor::lyapunov::{kaplan_yorke_dimension, Lyapunov, Spectrum};
+
+use crate::{params::Params, scene::Scene};
+
+/// How many steps the estimate advances by every frame, many more than the trajectories drawn
+/// as it takes a long time to settle
+const STEPS_PER_FRAME: usize = 5000;
+
+/// How many steps are taken between pulling the shadow trajectory back, or making the tangent
+/// vectors orthonormal
+const INTERVAL: usize = 10;
+
+pub struct ExponentsPlugin;
+
+impl Plugin for ExponentsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Exponents>()
+            .add_systems(Startup, spawn_panel)
+            .add_systems(
+                Update,
+                (
+                    toggle_exponents,
+                    restart_exponents.run_if(resource_changed::<Params>()),
+                    estimate_exponents,
+                    update_panel,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// The estimates, while they are running
+#[derive(Resource, Default)]
+struct Exponents(Option<(Lyapunov, Spectrum)>);
+
+#[derive(Component)]
+struct ExponentsPanel;
+
+/// Estimates along the scene's first trajectory, with the current parameters
+fn estimate(scene: &Scene, params: &[f64]) -> (Lyapunov, Spectrum) {
+    let mut simulation = scene.simulation(&scene.trajectories[0]);
+    simulation.set_params(params);
+    (
+        Lyapunov::new(simulation.clone(), scene.separation.epsilon, INTERVAL),
+        Spectrum::new(simulation, INTERVAL),
+    )
+}
+
+fn spawn_panel(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.),
+            right: Val::Px(10.),
+            ..default()
+        }),
+        ExponentsPanel,
+    ));
+}
+
+fn toggle_exponents(
+    keys: Res<Input<KeyCode>>,
+    scene: Res<Scene>,
+    params: Res<Params>,
+    mut exponents: ResMut<Exponents>,
+) {
+    if keys.just_pressed(KeyCode::L) {
+        exponents.0 = match exponents.0 {
+            Some(_) => None,
+            None => Some(estimate(&scene, params.params())),
+        };
+    }
+}
+
+fn restart_exponents(scene: Res<Scene>, params: Res<Params>, mut exponents: ResMut<Exponents>) {
+    if let Some(estimates) = &mut exponents.0 {
+        *estimates = estimate(&scene, params.params());
+    }
+}
+
+fn estimate_exponents(mut exponents: ResMut<Exponents>) {
+    if let Some((lyapunov, spectrum)) = &mut exponents.0 {
+        lyapunov.run(STEPS_PER_FRAME);
+        spectrum.run(STEPS_PER_FRAME);
+    }
+}
+
+fn update_panel(exponents: Res<Exponents>, mut panels: Query<&mut Text, With<ExponentsPanel>>) {
+    let text = match &exponents.0 {
+        Some((lyapunov, spectrum)) => match (lyapunov.exponent(), spectrum.exponents()) {
+            (Some(largest), Some(exponents)) => format!(
+                "largest Lyapunov exponent {largest:.3}\n\
+                 spectrum {:.3}, {:.3}, {:.3}\n\
+                 Kaplan-Yorke dimension {:.3}\n\
+                 over t = {:.0} (L)",
+                exponents[0],
+                exponents[1],
+                exponents[2],
+                kaplan_yorke_dimension(&exponents),
+                lyapunov.elapsed()
+            ),
+            _ => "Lyapunov exponents: estimating (L)".into(),
+        },
+        None => "Lyapunov exponents: off (L)".into(),
+    };
+
+    for mut panel in &mut panels {
+        panel.sections[0].value = text.clone();
+    }
+}
@@ -0,0 +1,355 @@
+//! Writing sampled trajectories out for analysis in other tools, and reading them back
+//!
+//! Besides the text formats there are two binary ones for long runs. `.npy` files hold an
+//! `N × 4` array of little endian `f64` rows `t, x, y, z` that NumPy loads directly. The
+//! native format stores the same rows after a header recording how they were produced:
+//!
+//! ```text
+//! magic       4 bytes  "LRNZ"
+//! version     u32      1
+//! system      string   e.g. "lorenz"
+//! params      u32 count followed by that many string names each with an f64 value
+//! dt          f64
+//! integrator  string   e.g. "rk4"
+//! samples     u64 number of rows
+//! rows        samples × (t, x, y, z) as f64
+//! ```
+//!
+//! Strings are a u32 length followed by that many bytes of UTF-8, and every number is little
+//! endian.
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use glam::DVec3;
+
+use crate::{Integrator, Simulation};
+
+const NATIVE_MAGIC: &[u8; 4] = b"LRNZ";
+/// The version of the native format written, the only one read
+pub const NATIVE_VERSION: u32 = 1;
+const NPY_MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+/// The formats a trajectory can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// Comma separated `t,x,y,z` columns with a header line
+    #[default]
+    Csv,
+    /// One `{"t":…,"x":…,"y":…,"z":…}` object per line
+    JsonLines,
+    /// A NumPy array file
+    Npy,
+    /// The native binary format, which also records the parameters
+    Binary,
+}
+
+impl Format {
+    pub const ALL: [Format; 4] = [Format::Csv, Format::JsonLines, Format::Npy, Format::Binary];
+
+    fn name(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::JsonLines => "jsonl",
+            Format::Npy => "npy",
+            Format::Binary => "bin",
+        }
+    }
+
+    /// The format a file is written in, going by its extension
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?;
+        extension.parse().ok()
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Format::ALL
+            .into_iter()
+            .find(|format| format.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Format::ALL.iter().map(|f| f.name()).collect();
+                format!(
+                    "unknown format `{s}`, expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// How a recorded trajectory was produced
+#[derive(Debug, Clone, PartialEq)]
+pub struct Header {
+    /// The name of the system
+    pub system: String,
+    /// The name and value of each parameter
+    pub params: Vec<(String, f64)>,
+    pub integrator: Integrator,
+    pub dt: f64,
+    /// The number of samples that follow
+    pub samples: u64,
+}
+
+impl Header {
+    /// The header for `samples` samples of `simulation`
+    pub fn new(simulation: &Simulation, samples: u64) -> Self {
+        let system = &simulation.system;
+        Self {
+            system: system.name().to_owned(),
+            params: system
+                .param_names()
+                .into_iter()
+                .map(str::to_owned)
+                .zip(system.params().iter().copied())
+                .collect(),
+            integrator: simulation.integrator,
+            dt: simulation.dt,
+            samples,
+        }
+    }
+}
+
+/// Writes the samples of a trajectory one at a time
+///
+/// The binary formats store the number of samples up front, so exactly as many as the
+/// header says have to be written before calling [`SampleWriter::finish`].
+pub struct SampleWriter<W: Write> {
+    writer: W,
+    format: Format,
+    remaining: u64,
+}
+
+impl<W: Write> SampleWriter<W> {
+    pub fn new(mut writer: W, format: Format, header: &Header) -> io::Result<Self> {
+        match format {
+            Format::Csv => writeln!(writer, "t,x,y,z")?,
+            Format::JsonLines => {}
+            Format::Npy => write_npy_header(&mut writer, header.samples)?,
+            Format::Binary => write_native_header(&mut writer, header)?,
+        }
+
+        Ok(Self {
+            writer,
+            format,
+            remaining: header.samples,
+        })
+    }
+
+    /// Writes the state `p` at time `t`
+    ///
+    /// Text formats use as many digits as it takes to read the numbers back exactly.
+    pub fn write(&mut self, t: f64, p: DVec3) -> io::Result<()> {
+        self.remaining = self.remaining.saturating_sub(1);
+
+        match self.format {
+            Format::Csv => writeln!(self.writer, "{t},{},{},{}", p.x, p.y, p.z),
+            Format::JsonLines => writeln!(
+                self.writer,
+                r#"{{"t":{t},"x":{},"y":{},"z":{}}}"#,
+                p.x, p.y, p.z
+            ),
+            Format::Npy | Format::Binary => {
+                for value in [t, p.x, p.y, p.z] {
+                    self.writer.write_all(&value.to_le_bytes())?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Flushes everything written and hands back the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        if matches!(self.format, Format::Npy | Format::Binary) && self.remaining != 0 {
+            return Err(invalid_data(format!(
+                "{} fewer samples were written than promised",
+                self.remaining
+            )));
+        }
+
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+fn write_npy_header(writer: &mut impl Write, samples: u64) -> io::Result<()> {
+    let mut dict = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': ({samples}, 4), }}");
+
+    // The magic, version and length take 10 bytes, and the whole header has to be padded
+    // with spaces to a multiple of 64 bytes ending in a newline
+    let unpadded = NPY_MAGIC.len() + 4 + dict.len() + 1;
+    dict.extend(std::iter::repeat_n(' ', (64 - unpadded % 64) % 64));
+    dict.push('\n');
+
+    writer.write_all(NPY_MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(dict.len() as u16).to_le_bytes())?;
+    writer.write_all(dict.as_bytes())
+}
+
+fn write_string(writer: &mut impl Write, s: &str) -> io::Result<()> {
+    writer.write_all(&(s.len() as u32).to_le_bytes())?;
+    writer.write_all(s.as_bytes())
+}
+
+fn write_native_header(writer: &mut impl Write, header: &Header) -> io::Result<()> {
+    writer.write_all(NATIVE_MAGIC)?;
+    writer.write_all(&NATIVE_VERSION.to_le_bytes())?;
+    write_string(writer, &header.system)?;
+    writer.write_all(&(header.params.len() as u32).to_le_bytes())?;
+    for (name, value) in &header.params {
+        write_string(writer, name)?;
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    writer.write_all(&header.dt.to_le_bytes())?;
+    write_string(writer, &header.integrator.to_string())?;
+    writer.write_all(&header.samples.to_le_bytes())
+}
+
+/// A trajectory read back from one of the binary formats
+#[derive(Debug, Clone)]
+pub struct Recording {
+    /// Only the native format records how the samples were produced
+    pub header: Option<Header>,
+    pub samples: Vec<(f64, DVec3)>,
+}
+
+impl Recording {
+    /// Reads either binary format, telling them apart by their first bytes
+    pub fn read(mut reader: impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+
+        let (header, samples) = if &magic == NATIVE_MAGIC {
+            let header = read_native_header(&mut reader)?;
+            let samples = header.samples;
+            (Some(header), samples)
+        } else if magic == NPY_MAGIC[..4] {
+            (None, read_npy_header(&mut reader)?)
+        } else {
+            return Err(invalid_data("not a .npy or native trajectory file"));
+        };
+
+        let mut rows = Vec::with_capacity(samples.min(1 << 24) as usize);
+        for _ in 0..samples {
+            let [t, x, y, z] = [(); 4].map(|_| read_f64(&mut reader));
+            rows.push((t?, DVec3::new(x?, y?, z?)));
+        }
+
+        Ok(Self {
+            header,
+            samples: rows,
+        })
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_f64(reader: &mut impl Read) -> io::Result<f64> {
+    read_bytes(reader).map(f64::from_le_bytes)
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    read_bytes(reader).map(u32::from_le_bytes)
+}
+
+fn read_string(reader: &mut impl Read, what: &str) -> io::Result<String> {
+    let mut bytes = vec![0; read_u32(reader)? as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| invalid_data(format!("the {what} is not UTF-8")))
+}
+
+fn read_native_header(reader: &mut impl Read) -> io::Result<Header> {
+    let version = read_u32(reader)?;
+    if version != NATIVE_VERSION {
+        return Err(invalid_data(format!(
+            "unsupported trajectory file version {version}"
+        )));
+    }
+
+    let system = read_string(reader, "system name")?;
+    let count = read_u32(reader)?;
+    let mut params = Vec::new();
+    for _ in 0..count {
+        params.push((read_string(reader, "parameter name")?, read_f64(reader)?));
+    }
+    let dt = read_f64(reader)?;
+
+    let integrator = read_string(reader, "integrator name")?
+        .parse()
+        .map_err(invalid_data)?;
+
+    let samples = read_bytes(reader).map(u64::from_le_bytes)?;
+
+    Ok(Header {
+        system,
+        params,
+        integrator,
+        dt,
+        samples,
+    })
+}
+
+/// Reads the rest of a `.npy` header after the first four bytes and returns the row count
+fn read_npy_header(reader: &mut impl Read) -> io::Result<u64> {
+    let [m4, m5, major, _minor] = read_bytes(reader)?;
+    if [m4, m5] != NPY_MAGIC[4..] {
+        return Err(invalid_data("not a .npy file"));
+    }
+
+    let len = match major {
+        1 => u16::from_le_bytes(read_bytes(reader)?) as usize,
+        2 | 3 => read_u32(reader)? as usize,
+        _ => return Err(invalid_data(format!("unsupported .npy version {major}"))),
+    };
+    let mut dict = vec![0; len];
+    reader.read_exact(&mut dict)?;
+    let dict = String::from_utf8_lossy(&dict);
+
+    if !dict.contains("'descr': '<f8'") || !dict.contains("'fortran_order': False") {
+        return Err(invalid_data(
+            "only C ordered little endian f64 .npy arrays can be read",
+        ));
+    }
+
+    // The shape is the only thing left to pick out, e.g. `'shape': (1000, 4), `
+    let shape = dict
+        .split_once("'shape': (")
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(shape, _)| shape)
+        .ok_or_else(|| invalid_data("the .npy header has no shape"))?;
+    let dimensions: Vec<_> = shape
+        .split(',')
+        .map(str::trim)
+        .filter(|dimension| !dimension.is_empty())
+        .collect();
+
+    match dimensions[..] {
+        [rows, "4"] => rows
+            .parse()
+            .map_err(|_| invalid_data(format!("`{rows}` is not a row count"))),
+        _ => Err(invalid_data(format!(
+            "expected an N × 4 array of t, x, y, z, found shape ({shape})"
+        ))),
+    }
+}
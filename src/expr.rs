@@ -0,0 +1,530 @@
+//! Arithmetic expressions typed in at runtime, such as `s * (y - x)`
+//!
+//! Expressions are made of numbers, the variables `x`, `y` and `z`, named parameters, the
+//! constants `pi` and `e`, the operators `+ - * / ^` and calls to common functions like
+//! `sin(x)` or `atan2(y, x)`. `^` binds tightest and groups to the right, so `-x^2` is
+//! `-(x^2)`.
+
+use std::fmt;
+
+use glam::DVec3;
+
+/// A mistake in an expression, at the character it was noticed at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Counted in characters from the start of the source, starting at zero
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    Ln,
+    Log10,
+    Sqrt,
+    Abs,
+    Sign,
+    Floor,
+    Ceil,
+    Min,
+    Max,
+    Pow,
+}
+
+impl Function {
+    const ALL: [Function; 21] = [
+        Function::Sin,
+        Function::Cos,
+        Function::Tan,
+        Function::Asin,
+        Function::Acos,
+        Function::Atan,
+        Function::Atan2,
+        Function::Sinh,
+        Function::Cosh,
+        Function::Tanh,
+        Function::Exp,
+        Function::Ln,
+        Function::Log10,
+        Function::Sqrt,
+        Function::Abs,
+        Function::Sign,
+        Function::Floor,
+        Function::Ceil,
+        Function::Min,
+        Function::Max,
+        Function::Pow,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Asin => "asin",
+            Function::Acos => "acos",
+            Function::Atan => "atan",
+            Function::Atan2 => "atan2",
+            Function::Sinh => "sinh",
+            Function::Cosh => "cosh",
+            Function::Tanh => "tanh",
+            Function::Exp => "exp",
+            Function::Ln => "ln",
+            Function::Log10 => "log10",
+            Function::Sqrt => "sqrt",
+            Function::Abs => "abs",
+            Function::Sign => "sign",
+            Function::Floor => "floor",
+            Function::Ceil => "ceil",
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Pow => "pow",
+        }
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Function::Atan2 | Function::Min | Function::Max | Function::Pow => 2,
+            _ => 1,
+        }
+    }
+
+    /// The value at `a`, and `b` for the functions of two arguments
+    fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            Function::Sin => a.sin(),
+            Function::Cos => a.cos(),
+            Function::Tan => a.tan(),
+            Function::Asin => a.asin(),
+            Function::Acos => a.acos(),
+            Function::Atan => a.atan(),
+            Function::Atan2 => a.atan2(b),
+            Function::Sinh => a.sinh(),
+            Function::Cosh => a.cosh(),
+            Function::Tanh => a.tanh(),
+            Function::Exp => a.exp(),
+            Function::Ln => a.ln(),
+            Function::Log10 => a.log10(),
+            Function::Sqrt => a.sqrt(),
+            Function::Abs => a.abs(),
+            // Unlike `f64::signum`, zero has no sign
+            Function::Sign => {
+                if a == 0. {
+                    0.
+                } else {
+                    a.signum()
+                }
+            }
+            Function::Floor => a.floor(),
+            Function::Ceil => a.ceil(),
+            Function::Min => a.min(b),
+            Function::Max => a.max(b),
+            Function::Pow => a.powf(b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(f64),
+    /// `x`, `y` or `z`, by index into the state
+    Variable(usize),
+    /// By index into the parameters the expression was parsed with
+    Param(usize),
+    Neg(Box<Node>),
+    Binary(Operator, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+/// A parsed expression, ready to be evaluated
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr(Node);
+
+impl Expr {
+    /// Parses `source`, in which `params` are the names of the parameters it may use
+    pub fn parse(source: &str, params: &[&str]) -> Result<Expr, ParseError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            next: 0,
+            end: source.chars().count(),
+            params,
+        };
+
+        let node = parser.sum()?;
+        match parser.peek() {
+            None => Ok(Expr(node)),
+            Some((position, token)) => Err(ParseError {
+                position,
+                message: format!("expected an operator, found {token}"),
+            }),
+        }
+    }
+
+    /// The value at the state `p`, with `params` in the order they were named when parsing
+    pub fn evaluate(&self, p: DVec3, params: &[f64]) -> f64 {
+        self.0.evaluate(p, params)
+    }
+
+    /// Whether the parameter at `index` appears anywhere in the expression
+    pub fn uses_param(&self, index: usize) -> bool {
+        self.0.uses_param(index)
+    }
+}
+
+impl Node {
+    fn evaluate(&self, p: DVec3, params: &[f64]) -> f64 {
+        match self {
+            Node::Number(value) => *value,
+            Node::Variable(index) => p[*index],
+            Node::Param(index) => params[*index],
+            Node::Neg(expr) => -expr.evaluate(p, params),
+            Node::Binary(operator, left, right) => {
+                let (a, b) = (left.evaluate(p, params), right.evaluate(p, params));
+                match operator {
+                    Operator::Add => a + b,
+                    Operator::Sub => a - b,
+                    Operator::Mul => a * b,
+                    Operator::Div => a / b,
+                    Operator::Pow => pow(a, b),
+                }
+            }
+            Node::Call(function, args) => {
+                let a = args[0].evaluate(p, params);
+                let b = args.get(1).map_or(0., |arg| arg.evaluate(p, params));
+                function.apply(a, b)
+            }
+        }
+    }
+
+    fn uses_param(&self, index: usize) -> bool {
+        match self {
+            Node::Param(param) => *param == index,
+            Node::Number(_) | Node::Variable(_) => false,
+            Node::Neg(expr) => expr.uses_param(index),
+            Node::Binary(_, left, right) => left.uses_param(index) || right.uses_param(index),
+            Node::Call(_, args) => args.iter().any(|arg| arg.uses_param(index)),
+        }
+    }
+}
+
+/// `a^b`, multiplying out small whole powers which is both faster and exact for negative `a`
+fn pow(a: f64, b: f64) -> f64 {
+    if b.fract() == 0. && b.abs() <= 16. {
+        a.powi(b as i32)
+    } else {
+        a.powf(b)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Operator(Operator),
+    Open,
+    Close,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "the number {value}"),
+            Token::Name(name) => write!(f, "`{name}`"),
+            Token::Operator(operator) => {
+                let symbol = match operator {
+                    Operator::Add => '+',
+                    Operator::Sub => '-',
+                    Operator::Mul => '*',
+                    Operator::Div => '/',
+                    Operator::Pow => '^',
+                };
+                write!(f, "`{symbol}`")
+            }
+            Token::Open => f.write_str("`(`"),
+            Token::Close => f.write_str("`)`"),
+            Token::Comma => f.write_str("`,`"),
+        }
+    }
+}
+
+/// Splits `source` into tokens, each with the position of its first character
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+
+        let token = match c {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '+' => Token::Operator(Operator::Add),
+            '-' => Token::Operator(Operator::Sub),
+            '*' => Token::Operator(Operator::Mul),
+            '/' => Token::Operator(Operator::Div),
+            '^' => Token::Operator(Operator::Pow),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            _ if c.is_ascii_digit() || c == '.' => {
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                // An exponent, as long as it is followed by digits
+                if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                    let mut j = i + 1;
+                    if j < chars.len() && matches!(chars[j], '+' | '-') {
+                        j += 1;
+                    }
+                    if j < chars.len() && chars[j].is_ascii_digit() {
+                        i = j;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+
+                let number: String = chars[start..i].iter().collect();
+                let value = number.parse().map_err(|_| ParseError {
+                    position: start,
+                    message: format!("`{number}` is not a number"),
+                })?;
+                tokens.push((start, Token::Number(value)));
+                continue;
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push((start, Token::Name(chars[start..i].iter().collect())));
+                continue;
+            }
+            _ => {
+                return Err(ParseError {
+                    position: start,
+                    message: format!("unexpected character `{c}`"),
+                })
+            }
+        };
+
+        tokens.push((start, token));
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+/// A recursive descent parser with one function for each level of precedence
+struct Parser<'a> {
+    tokens: &'a [(usize, Token)],
+    next: usize,
+    /// The position just past the end of the source, where running out of tokens is reported
+    end: usize,
+    params: &'a [&'a str],
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<(usize, &Token)> {
+        self.tokens
+            .get(self.next)
+            .map(|(position, token)| (*position, token))
+    }
+
+    fn advance(&mut self) -> Option<(usize, &'a Token)> {
+        let tokens = self.tokens;
+        let token = tokens.get(self.next)?;
+        self.next += 1;
+        Some((token.0, &token.1))
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        let found = matches!(self.peek(), Some((_, token)) if token == expected);
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ParseError> {
+        if self.eat(&expected) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("{expected}")))
+        }
+    }
+
+    /// An error about the next token, or the end of the source, not being what was expected
+    fn unexpected(&self, expected: &str) -> ParseError {
+        match self.peek() {
+            Some((position, token)) => ParseError {
+                position,
+                message: format!("expected {expected}, found {token}"),
+            },
+            None => ParseError {
+                position: self.end,
+                message: format!("expected {expected}, found the end of the expression"),
+            },
+        }
+    }
+
+    /// `a + b - c …`
+    fn sum(&mut self) -> Result<Node, ParseError> {
+        let mut expr = self.product()?;
+        loop {
+            let operator = match self.peek() {
+                Some((_, Token::Operator(operator @ (Operator::Add | Operator::Sub)))) => *operator,
+                _ => return Ok(expr),
+            };
+            self.next += 1;
+            expr = Node::Binary(operator, Box::new(expr), Box::new(self.product()?));
+        }
+    }
+
+    /// `a * b / c …`
+    fn product(&mut self) -> Result<Node, ParseError> {
+        let mut expr = self.unary()?;
+        loop {
+            let operator = match self.peek() {
+                Some((_, Token::Operator(operator @ (Operator::Mul | Operator::Div)))) => *operator,
+                _ => return Ok(expr),
+            };
+            self.next += 1;
+            expr = Node::Binary(operator, Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    /// `-a` or `+a`
+    fn unary(&mut self) -> Result<Node, ParseError> {
+        if self.eat(&Token::Operator(Operator::Sub)) {
+            Ok(Node::Neg(Box::new(self.unary()?)))
+        } else if self.eat(&Token::Operator(Operator::Add)) {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    /// `a ^ b`, where `b` may itself be a power or negated
+    fn power(&mut self) -> Result<Node, ParseError> {
+        let base = self.atom()?;
+        if self.eat(&Token::Operator(Operator::Pow)) {
+            let exponent = self.unary()?;
+            Ok(Node::Binary(
+                Operator::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    /// A number, a name, a function call or an expression in brackets
+    fn atom(&mut self) -> Result<Node, ParseError> {
+        let Some((position, token)) = self.advance() else {
+            return Err(self.unexpected("a number, a name or `(`"));
+        };
+
+        match token.clone() {
+            Token::Number(value) => Ok(Node::Number(value)),
+            Token::Open => {
+                let expr = self.sum()?;
+                self.expect(Token::Close)?;
+                Ok(expr)
+            }
+            Token::Name(name) if self.eat(&Token::Open) => self.call(position, &name),
+            Token::Name(name) => self.name(position, &name),
+            _ => {
+                self.next -= 1;
+                Err(self.unexpected("a number, a name or `(`"))
+            }
+        }
+    }
+
+    fn name(&self, position: usize, name: &str) -> Result<Node, ParseError> {
+        if let Some(index) = self.params.iter().position(|param| *param == name) {
+            return Ok(Node::Param(index));
+        }
+
+        match name {
+            "x" => Ok(Node::Variable(0)),
+            "y" => Ok(Node::Variable(1)),
+            "z" => Ok(Node::Variable(2)),
+            "pi" => Ok(Node::Number(std::f64::consts::PI)),
+            "e" => Ok(Node::Number(std::f64::consts::E)),
+            _ if Function::ALL.iter().any(|function| function.name() == name) => Err(ParseError {
+                position,
+                message: format!("the function `{name}` has to be called, as in `{name}(x)`"),
+            }),
+            _ => Err(ParseError {
+                position,
+                message: format!("unknown variable `{name}`"),
+            }),
+        }
+    }
+
+    /// The arguments of a call to `name`, after its opening bracket
+    fn call(&mut self, position: usize, name: &str) -> Result<Node, ParseError> {
+        let function = Function::ALL
+            .into_iter()
+            .find(|function| function.name() == name)
+            .ok_or_else(|| ParseError {
+                position,
+                message: format!("unknown function `{name}`"),
+            })?;
+
+        let mut args = vec![self.sum()?];
+        while self.eat(&Token::Comma) {
+            args.push(self.sum()?);
+        }
+        self.expect(Token::Close)?;
+
+        if args.len() != function.arity() {
+            return Err(ParseError {
+                position,
+                message: format!(
+                    "`{name}` takes {} argument{}, found {}",
+                    function.arity(),
+                    if function.arity() == 1 { "" } else { "s" },
+                    args.len()
+                ),
+            });
+        }
+
+        Ok(Node::Call(function, args))
+    }
+}
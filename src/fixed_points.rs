@@ -0,0 +1,155 @@
+//! Markers on the equilibria of the system, labelled with what the flow does around each
+//!
+//! Each equilibrium gets a small sphere, green when it draws every nearby trajectory in,
+//! red when it pushes them all away and orange for a saddle, and a label with its kind and the
+//! eigenvalues of the Jacobian there. They are found again whenever a parameter changes, and
+//! E shows or hides them.
+
+use bevy::prelude::*;
+use bevy_flycam::prelude::FlyCam;
+use lorenz_attractor::{
+    equilibria::{self, Equilibrium, Kind},
+    DynamicalSystem,
+};
+
+use crate::{params::Params, scene::Scene};
+
+pub struct FixedPointsPlugin;
+
+impl Plugin for FixedPointsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_equilibria).add_systems(
+            Update,
+            (
+                toggle_equilibria,
+                find_equilibria.run_if(resource_changed::<Params>()),
+                (draw_markers, place_labels),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The equilibria for the current parameters
+#[derive(Resource)]
+struct Equilibria {
+    show: bool,
+    found: Vec<Equilibrium>,
+}
+
+/// The label of the equilibrium at this index into [`Equilibria::found`]
+#[derive(Component)]
+struct EquilibriumLabel(usize);
+
+/// The colour an equilibrium of `kind` is marked in
+fn color(kind: Kind) -> Color {
+    match kind {
+        Kind::Node { stable: true } | Kind::Spiral { stable: true } => Color::rgb(0.3, 0.9, 0.4),
+        Kind::Node { stable: false } | Kind::Spiral { stable: false } => Color::rgb(1., 0.3, 0.3),
+        Kind::Saddle { .. } => Color::rgb(1., 0.65, 0.2),
+        Kind::NonHyperbolic => Color::rgb(0.9, 0.9, 0.3),
+    }
+}
+
+/// The radius of the markers, small next to the attractor
+fn radius(system: &dyn DynamicalSystem) -> f32 {
+    let framing = system.framing();
+    let width = |[from, to]: [f64; 2]| (to - from) as f32;
+    0.015 * width(framing.x).max(width(framing.y))
+}
+
+fn setup_equilibria(mut commands: Commands, scene: Res<Scene>) {
+    commands.insert_resource(Equilibria {
+        show: scene.equilibria.show,
+        found: Vec::new(),
+    });
+}
+
+fn toggle_equilibria(keys: Res<Input<KeyCode>>, mut equilibria: ResMut<Equilibria>) {
+    if keys.just_pressed(KeyCode::E) {
+        equilibria.show = !equilibria.show;
+    }
+}
+
+fn find_equilibria(
+    mut commands: Commands,
+    params: Res<Params>,
+    mut equilibria: ResMut<Equilibria>,
+    labels: Query<Entity, With<EquilibriumLabel>>,
+) {
+    equilibria.found = equilibria::equilibria(&*params.0);
+
+    for label in &labels {
+        commands.entity(label).despawn();
+    }
+    for (index, equilibrium) in equilibria.found.iter().enumerate() {
+        let eigenvalues: Vec<_> = equilibrium
+            .distinct_eigenvalues()
+            .map(|eigenvalue| format!("{eigenvalue:.2}"))
+            .collect();
+        let mut label = TextBundle::from_section(
+            format!("{}\nλ = {}", equilibrium.kind, eigenvalues.join(", ")),
+            TextStyle {
+                font_size: 14.,
+                color: color(equilibrium.kind),
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            ..default()
+        });
+        // Until it is placed next to its marker
+        label.visibility = Visibility::Hidden;
+        commands.spawn((label, EquilibriumLabel(index)));
+    }
+}
+
+fn draw_markers(mut gizmos: Gizmos, params: Res<Params>, equilibria: Res<Equilibria>) {
+    if !equilibria.show {
+        return;
+    }
+    let radius = radius(&*params.0);
+    for equilibrium in &equilibria.found {
+        let position = equilibrium.point.as_vec3();
+        gizmos
+            .sphere(position, Quat::IDENTITY, radius, color(equilibrium.kind))
+            .circle_segments(16);
+    }
+}
+
+/// Keeps each label next to its marker on screen, hidden while the marker is out of view
+fn place_labels(
+    params: Res<Params>,
+    equilibria: Res<Equilibria>,
+    cameras: Query<(&Camera, &GlobalTransform), With<FlyCam>>,
+    mut labels: Query<(&EquilibriumLabel, &mut Style, &mut Visibility)>,
+) {
+    let Ok((camera, transform)) = cameras.get_single() else {
+        return;
+    };
+    let radius = radius(&*params.0);
+
+    for (EquilibriumLabel(index), mut style, mut visibility) in &mut labels {
+        let position = equilibria
+            .found
+            .get(*index)
+            .filter(|_| equilibria.show)
+            .and_then(|equilibrium| {
+                // Beside the marker, however close the camera is
+                let point = equilibrium.point.as_vec3();
+                let center = camera.world_to_viewport(transform, point)?;
+                let edge = camera.world_to_viewport(transform, point + transform.right() * radius);
+                let offset = edge.map_or(0., |edge| center.distance(edge));
+                Some(center + Vec2::new(offset + 6., -8.))
+            });
+        match position {
+            Some(position) => {
+                style.left = Val::Px(position.x);
+                style.top = Val::Px(position.y);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
@@ -0,0 +1,537 @@
+//! The subcommands that run without opening a window
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use bevy::math::DVec3;
+use lorenz_attractor::{
+    bifurcation::{self, Method, Sweep},
+    dimension::{self, CorrelationIntegral},
+    divergence::{fit_growth, Divergence},
+    equilibria,
+    export::{Format, Header, SampleWriter},
+    lyapunov::{kaplan_yorke_dimension, Lyapunov, Spectrum},
+    maxima,
+    orbits::Search,
+    power_spectrum::PowerSpectrum,
+    symbols::{self, Wing},
+    Simulation,
+};
+
+use crate::{
+    cli::{
+        BifurcationArgs, Command, DimensionArgs, ExportArgs, LyapunovArgs, OrbitsArgs,
+        PoincareArgs, PowerSpectrumArgs, ReturnMapArgs, SeparationArgs, SymbolsArgs,
+    },
+    replay::Recorded,
+    scene::Scene,
+};
+
+pub fn run(command: &Command, scene: &Scene) -> Result<(), String> {
+    match command {
+        Command::Export(args) => export(args, scene),
+        Command::Separation(args) => separation(args, scene),
+        Command::Lyapunov(args) => lyapunov(args, scene),
+        Command::Poincare(args) => poincare(args, scene),
+        Command::ReturnMap(args) => return_map(args, scene),
+        Command::Bifurcation(args) => bifurcation(args, scene),
+        Command::Orbits(args) => orbits(args, scene),
+        Command::Equilibria => equilibria(scene),
+        Command::Symbols(args) => symbols(args, scene),
+        Command::PowerSpectrum(args) => power_spectrum(args, scene),
+        Command::Dimension(args) => dimension(args, scene),
+    }
+}
+
+/// The simulation of the scene's trajectory at `index`
+fn simulation(scene: &Scene, index: usize) -> Result<Simulation, String> {
+    let trajectory = scene.trajectories.get(index).ok_or_else(|| {
+        format!(
+            "there is no trajectory {index}, the scene has {}",
+            scene.trajectories.len()
+        )
+    })?;
+    Ok(scene.simulation(trajectory))
+}
+
+/// Treats whatever was reading stdout having seen all it wanted as success
+fn finish_write(result: io::Result<()>, what: &str) -> Result<(), String> {
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|err| format!("could not write the {what}: {err}")),
+    }
+}
+
+/// Opens the file at `path` for writing, or stdout without one
+fn output(path: Option<&Path>) -> Result<Box<dyn Write>, String> {
+    match path {
+        Some(path) => File::create(path)
+            .map(|file| Box::new(BufWriter::new(file)) as Box<dyn Write>)
+            .map_err(|err| format!("could not create {}: {err}", path.display())),
+        None => Ok(Box::new(BufWriter::new(io::stdout().lock()))),
+    }
+}
+
+fn export(args: &ExportArgs, scene: &Scene) -> Result<(), String> {
+    let mut simulation = simulation(scene, args.trajectory)?;
+    let output = output(args.output.as_deref())?;
+
+    let format = args
+        .format
+        .or_else(|| args.output.as_deref().and_then(Format::from_path))
+        .unwrap_or_default();
+    let header = Header::new(&simulation, args.steps as u64 + 1);
+
+    let write = || -> io::Result<()> {
+        let mut writer = SampleWriter::new(output, format, &header)?;
+        for (t, p) in simulation.samples(args.steps) {
+            writer.write(t, p)?;
+        }
+        writer.finish()?;
+        Ok(())
+    };
+
+    let written = write();
+    simulation.check()?;
+    finish_write(written, "trajectory")
+}
+
+fn separation(args: &SeparationArgs, scene: &Scene) -> Result<(), String> {
+    let offset = DVec3::X * scene.separation.epsilon;
+    let mut divergence = Divergence::new(simulation(scene, args.trajectory)?, offset);
+    let samples: Vec<_> = divergence.samples(args.steps).collect();
+    divergence.check()?;
+    let mut output = output(args.output.as_deref())?;
+
+    let mut write = || -> io::Result<()> {
+        writeln!(output, "t,separation")?;
+        for (t, d) in &samples {
+            writeln!(output, "{t},{d}")?;
+        }
+        output.flush()
+    };
+    finish_write(write(), "separation")?;
+
+    match fit_growth(&samples) {
+        Some(fit) => eprintln!(
+            "growth rate {:.4} per unit of time, fitted up to t = {:.2}",
+            fit.rate, fit.until
+        ),
+        None => eprintln!("the separation did not grow enough to fit its rate"),
+    }
+    Ok(())
+}
+
+fn lyapunov(args: &LyapunovArgs, scene: &Scene) -> Result<(), String> {
+    let mut simulation = simulation(scene, args.trajectory)?;
+    for _ in 0..args.transient {
+        simulation.step();
+    }
+    simulation.check()?;
+    let too_few_steps = "there were too few steps to reach the end of an interval even once";
+
+    if args.spectrum {
+        let mut spectrum = Spectrum::new(simulation, args.interval as usize);
+        let exponents = spectrum.run(args.steps).ok_or(too_few_steps)?;
+        if !exponents.iter().all(|exponent| exponent.is_finite()) {
+            return Err("the exponents stopped being finite, the solution may blow up".into());
+        }
+        println!(
+            "Lyapunov exponents {:.4}, {:.4}, {:.4}, over t = {:.2}",
+            exponents[0],
+            exponents[1],
+            exponents[2],
+            spectrum.elapsed()
+        );
+        println!(
+            "Kaplan-Yorke dimension {:.4}",
+            kaplan_yorke_dimension(&exponents)
+        );
+    } else {
+        let mut lyapunov =
+            Lyapunov::new(simulation, scene.separation.epsilon, args.interval as usize);
+        let exponent = lyapunov.run(args.steps);
+        lyapunov.check()?;
+        let exponent = exponent.ok_or(too_few_steps)?;
+        println!(
+            "largest Lyapunov exponent {exponent:.4}, over t = {:.2}",
+            lyapunov.elapsed()
+        );
+    }
+    Ok(())
+}
+
+fn poincare(args: &PoincareArgs, scene: &Scene) -> Result<(), String> {
+    let mut simulation = simulation(scene, args.trajectory)?;
+    for _ in 0..args.transient {
+        simulation.step();
+    }
+    let section = scene.poincare.section(&*simulation.system)?;
+    let mut output = output(args.output.as_deref())?;
+
+    let mut count = 0;
+    let mut write = || -> io::Result<()> {
+        writeln!(output, "t,x,y,z,u,v")?;
+        for crossing in section.crossings(&mut simulation, args.steps) {
+            let p = crossing.point;
+            let along = section.plane.coordinates(p);
+            writeln!(
+                output,
+                "{},{},{},{},{},{}",
+                crossing.time, p.x, p.y, p.z, along.x, along.y
+            )?;
+            count += 1;
+        }
+        output.flush()
+    };
+    let written = write();
+    simulation.check()?;
+    finish_write(written, "crossings")?;
+
+    eprintln!("{count} crossings of the plane");
+    Ok(())
+}
+
+fn return_map(args: &ReturnMapArgs, scene: &Scene) -> Result<(), String> {
+    let mut simulation = simulation(scene, args.trajectory)?;
+    for _ in 0..args.transient {
+        simulation.step();
+    }
+    let peaks: Vec<_> = maxima::maxima(&mut simulation, args.steps).collect();
+    simulation.check()?;
+    let mut output = output(args.output.as_deref())?;
+
+    let mut write = || -> io::Result<()> {
+        writeln!(output, "t,z,next")?;
+        for (maximum, (z, next)) in peaks.iter().zip(maxima::return_map(&peaks)) {
+            writeln!(output, "{},{z},{next}", maximum.time)?;
+        }
+        output.flush()
+    };
+    finish_write(write(), "return map")?;
+
+    eprintln!("{} maxima of z", peaks.len());
+    Ok(())
+}
+
+fn bifurcation(args: &BifurcationArgs, scene: &Scene) -> Result<(), String> {
+    let simulation = simulation(scene, args.trajectory)?;
+    let system = &*simulation.system;
+    let param = system.param_index(&args.sweep).ok_or_else(|| {
+        format!(
+            "{} has no parameter `{}`, expected one of: {}",
+            system.name(),
+            args.sweep,
+            system.param_names().join(", ")
+        )
+    })?;
+    let sweep = Sweep {
+        param,
+        from: args.from,
+        to: args.to,
+        count: args.count as usize,
+        transient: args.transient,
+        steps: args.steps,
+    };
+    let columns = sweep.run(&simulation, args.method, |system| {
+        scene.poincare.section(system)
+    })?;
+    let mut output = output(args.output.as_deref())?;
+
+    let value = match args.method {
+        Method::Maxima => "z",
+        Method::Section => "u",
+    };
+    let mut write = || -> io::Result<()> {
+        writeln!(output, "{},{value}", args.sweep)?;
+        for column in &columns {
+            for value in &column.values {
+                writeln!(output, "{},{value}", column.param)?;
+            }
+        }
+        output.flush()
+    };
+    finish_write(write(), "bifurcation diagram")?;
+
+    if let Some(path) = &args.image {
+        let (width, height) = (args.width, args.height);
+        let pixels = bifurcation::rasterise(
+            &columns,
+            (args.from, args.to),
+            width as usize,
+            height as usize,
+        );
+        let write = || -> Result<(), png::EncodingError> {
+            let file = BufWriter::new(File::create(path)?);
+            let mut encoder = png::Encoder::new(file, width, height);
+            encoder.set_color(png::ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.write_header()?.write_image_data(&pixels)
+        };
+        write().map_err(|err| format!("could not write {}: {err}", path.display()))?;
+    }
+
+    let points: usize = columns.iter().map(|column| column.values.len()).sum();
+    eprintln!(
+        "{points} points over {} values of {}",
+        columns.len(),
+        args.sweep
+    );
+    Ok(())
+}
+
+fn orbits(args: &OrbitsArgs, scene: &Scene) -> Result<(), String> {
+    let simulation = simulation(scene, args.trajectory)?;
+    let search = Search {
+        section: scene.poincare.section(&*simulation.system)?,
+        max_crossings: scene.orbits.max_crossings,
+        transient: args.transient,
+        steps: args.steps,
+        closeness: args.closeness,
+        attempts: args.attempts,
+    };
+    let found = search.run(simulation)?;
+    let mut output = output(args.output.as_deref())?;
+
+    let mut write = || -> io::Result<()> {
+        writeln!(
+            output,
+            "sequence,crossings,period,multiplier,exponent,x,y,z"
+        )?;
+        for orbit in &found {
+            let p = orbit.start;
+            writeln!(
+                output,
+                "{},{},{},{},{},{},{},{}",
+                orbit.sequence,
+                orbit.crossings(),
+                orbit.period,
+                orbit.multiplier,
+                orbit.exponent(),
+                p.x,
+                p.y,
+                p.z
+            )?;
+        }
+        output.flush()
+    };
+    finish_write(write(), "orbits")?;
+
+    eprintln!(
+        "{} periodic orbits within {} crossings of the plane",
+        found.len(),
+        scene.orbits.max_crossings
+    );
+    Ok(())
+}
+
+fn symbols(args: &SymbolsArgs, scene: &Scene) -> Result<(), String> {
+    let mut simulation = simulation(scene, args.trajectory)?;
+    for _ in 0..args.transient {
+        simulation.step();
+    }
+    let mut output = output(args.output.as_deref())?;
+
+    // Each loop is written out as soon as it is found, only its wing is kept for the entropy
+    let mut wings: Vec<Wing> = Vec::new();
+    let mut write = || -> io::Result<()> {
+        writeln!(output, "t,x,z,wing")?;
+        for (maximum, wing) in symbols::loops(&mut simulation, args.steps) {
+            let p = maximum.point;
+            writeln!(output, "{},{},{},{wing}", maximum.time, p.x, p.z)?;
+            wings.push(wing);
+        }
+        output.flush()
+    };
+    let written = write();
+    simulation.check()?;
+    finish_write(written, "symbols")?;
+
+    let left = wings.iter().filter(|&&wing| wing == Wing::Left).count();
+    eprintln!(
+        "{} loops, {left} around the left wing and {} around the right",
+        wings.len(),
+        wings.len() - left
+    );
+    eprintln!("block entropy in bits, and what each loop longer adds:");
+    let mut previous = 0.;
+    for length in 1..=args.blocks as usize {
+        let entropy = symbols::block_entropy(&wings, length);
+        eprintln!(
+            "  blocks of {length:>2}: {entropy:.4}, +{:.4}",
+            entropy - previous
+        );
+        previous = entropy;
+    }
+    let runs: Vec<_> = symbols::run_lengths(&wings)
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, count)| count > 0)
+        .map(|(length, count)| format!("{length}: {count}"))
+        .collect();
+    eprintln!("runs of loops on one wing, by length: {}", runs.join(", "));
+    Ok(())
+}
+
+fn power_spectrum(args: &PowerSpectrumArgs, scene: &Scene) -> Result<(), String> {
+    let every = args
+        .every
+        .map_or(scene.power_spectrum.every, |every| every as usize);
+    let (samples, interval): (Vec<DVec3>, f64) = match &args.input {
+        Some(path) => {
+            let samples = Recorded::load(path)?.0.samples;
+            let interval = match samples[..] {
+                [(first, _), .., (last, _)] => (last - first) / (samples.len() - 1) as f64,
+                _ => 0.,
+            };
+            let samples = samples.into_iter().step_by(every).map(|(_, p)| p);
+            (samples.collect(), interval * every as f64)
+        }
+        None => {
+            let mut simulation = simulation(scene, args.trajectory)?;
+            for _ in 0..args.transient {
+                simulation.step();
+            }
+            let samples = (0..args.steps / every).map(|_| {
+                for _ in 0..every {
+                    simulation.step();
+                }
+                simulation.state
+            });
+            let samples = samples.collect();
+            simulation.check()?;
+            (samples, simulation.dt * every as f64)
+        }
+    };
+
+    let segment = scene.power_spectrum.segment;
+    let spectrum = PowerSpectrum::new(&samples, interval, scene.power_spectrum.window, segment)
+        .ok_or_else(|| {
+            format!(
+                "{} samples are too few for a segment of {segment}",
+                samples.len()
+            )
+        })?;
+    let mut output = output(args.output.as_deref())?;
+
+    let mut write = || -> io::Result<()> {
+        writeln!(output, "frequency,x,y,z")?;
+        for (f, d) in spectrum.frequencies().zip(&spectrum.density) {
+            writeln!(output, "{f},{},{},{}", d.x, d.y, d.z)?;
+        }
+        output.flush()
+    };
+    finish_write(write(), "power spectrum")?;
+
+    let peaks = spectrum.peaks();
+    eprintln!(
+        "{} segments of {segment} samples {interval} apart, a {} window",
+        spectrum.segments, scene.power_spectrum.window
+    );
+    eprintln!(
+        "most power at f = {:.4} in x, {:.4} in y and {:.4} in z, to within {:.4}",
+        peaks.x, peaks.y, peaks.z, spectrum.resolution
+    );
+    Ok(())
+}
+
+fn dimension(args: &DimensionArgs, scene: &Scene) -> Result<(), String> {
+    let (count, every) = (args.points as usize, args.every as usize);
+    let points: Vec<DVec3> = match &args.input {
+        Some(path) => {
+            let recorded = Recorded::load(path)?;
+            recorded
+                .0
+                .samples
+                .into_iter()
+                .step_by(every)
+                .take(count)
+                .map(|(_, p)| p)
+                .collect()
+        }
+        None => {
+            let mut simulation = simulation(scene, args.trajectory)?;
+            for _ in 0..args.transient {
+                simulation.step();
+            }
+            let points = (0..count)
+                .map(|_| {
+                    for _ in 0..every {
+                        simulation.step();
+                    }
+                    simulation.state
+                })
+                .collect();
+            simulation.check()?;
+            points
+        }
+    };
+    if points.len() < 2 {
+        return Err("at least two points are needed".into());
+    }
+
+    let (low, high) = points.iter().fold(
+        (DVec3::splat(f64::INFINITY), DVec3::splat(f64::NEG_INFINITY)),
+        |(low, high), &p| (low.min(p), high.max(p)),
+    );
+    let size = low.distance(high);
+    let from = args.from.unwrap_or(size * 1e-3);
+    let to = args.to.unwrap_or(size * 0.03);
+    if from >= to {
+        return Err(format!("the radii must grow, {from} is not below {to}"));
+    }
+    let integral = CorrelationIntegral::new(
+        &points,
+        dimension::radii(from, to, args.count as usize),
+        args.theiler,
+    );
+    let mut output = output(args.output.as_deref())?;
+
+    let mut write = || -> io::Result<()> {
+        writeln!(output, "r,pairs,c")?;
+        for ((r, c), pairs) in integral.values().zip(&integral.pairs) {
+            writeln!(output, "{r},{pairs},{c}")?;
+        }
+        output.flush()
+    };
+    finish_write(write(), "correlation integral")?;
+
+    match integral.fit(args.width as usize) {
+        Some(fit) => eprintln!(
+            "correlation dimension {:.3} over {} points, fitted from r = {:.3} to {:.3}",
+            fit.dimension,
+            points.len(),
+            fit.from,
+            fit.to
+        ),
+        None => eprintln!("too few pairs to fit the correlation dimension"),
+    }
+    Ok(())
+}
+
+fn equilibria(scene: &Scene) -> Result<(), String> {
+    let system = scene.system();
+    let found = equilibria::equilibria(&*system);
+    for equilibrium in &found {
+        let p = equilibrium.point;
+        let eigenvalues: Vec<_> = equilibrium
+            .distinct_eigenvalues()
+            .map(|eigenvalue| format!("{eigenvalue:.4}"))
+            .collect();
+        println!(
+            "{:.6}, {:.6}, {:.6}: {}, eigenvalues {}",
+            p.x,
+            p.y,
+            p.z,
+            equilibrium.kind,
+            eigenvalues.join(", ")
+        );
+    }
+    if found.is_empty() {
+        eprintln!("no equilibria were found");
+    }
+    Ok(())
+}
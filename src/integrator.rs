@@ -0,0 +1,99 @@
+//! Numerical schemes for advancing an autonomous ODE `dp/dt = f(p)`
+
+use std::{fmt, str::FromStr};
+
+use glam::DVec3;
+
+use crate::adaptive;
+
+/// The scheme used to advance the state by one time step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Integrator {
+    /// First order forward Euler
+    Euler,
+    /// Second order explicit midpoint method
+    Midpoint,
+    /// The classic fourth order Runge-Kutta method
+    #[default]
+    RungeKutta4,
+    /// The fifth order Dormand–Prince method with adaptive step size control, see
+    /// [`adaptive::DormandPrince`]
+    DormandPrince,
+}
+
+impl Integrator {
+    pub const ALL: [Integrator; 4] = [
+        Integrator::Euler,
+        Integrator::Midpoint,
+        Integrator::RungeKutta4,
+        Integrator::DormandPrince,
+    ];
+
+    /// Whether the scheme picks its own step sizes rather than stepping by `dt`
+    pub fn is_adaptive(self) -> bool {
+        self == Integrator::DormandPrince
+    }
+
+    /// The order of accuracy, the global error shrinks with `dt.powi(order)`
+    pub fn order(self) -> i32 {
+        match self {
+            Integrator::Euler => 1,
+            Integrator::Midpoint => 2,
+            Integrator::RungeKutta4 => 4,
+            Integrator::DormandPrince => 5,
+        }
+    }
+
+    /// Advances `p` by a single step of size `dt` through the flow `f`
+    ///
+    /// Adaptive schemes take one step of their highest order solution without error control.
+    pub fn step(self, f: impl Fn(DVec3) -> DVec3, p: DVec3, dt: f64) -> DVec3 {
+        match self {
+            Integrator::Euler => p + f(p) * dt,
+            Integrator::Midpoint => {
+                let k1 = f(p);
+                p + f(p + k1 * (dt / 2.)) * dt
+            }
+            Integrator::RungeKutta4 => {
+                let k1 = f(p);
+                let k2 = f(p + k1 * (dt / 2.));
+                let k3 = f(p + k2 * (dt / 2.));
+                let k4 = f(p + k3 * dt);
+                p + (k1 + 2. * k2 + 2. * k3 + k4) * (dt / 6.)
+            }
+            Integrator::DormandPrince => adaptive::fixed_step(f, p, dt),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Integrator::Euler => "euler",
+            Integrator::Midpoint => "midpoint",
+            Integrator::RungeKutta4 => "rk4",
+            Integrator::DormandPrince => "rk45",
+        }
+    }
+}
+
+impl fmt::Display for Integrator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Integrator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Integrator::ALL
+            .into_iter()
+            .find(|integrator| integrator.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Integrator::ALL.iter().map(|i| i.name()).collect();
+                format!(
+                    "unknown integrator `{s}`, expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
@@ -0,0 +1,31 @@
+//! The simulation behind the Lorenz attractor visualisation, and the other chaotic flows it
+//! can draw
+//!
+//! Nothing in this crate depends on Bevy, so it can be driven from the renderer,
+//! a command line tool or a test alike.
+
+pub mod adaptive;
+pub mod attractors;
+pub mod bifurcation;
+pub mod dimension;
+pub mod divergence;
+pub mod equations;
+pub mod equilibria;
+pub mod export;
+pub mod expr;
+pub mod integrator;
+pub mod lyapunov;
+pub mod maxima;
+pub mod orbits;
+pub mod perturbation;
+pub mod poincare;
+pub mod power_spectrum;
+pub mod simulation;
+pub mod symbols;
+pub mod system;
+
+pub use attractors::Attractor;
+pub use equations::Equations;
+pub use integrator::Integrator;
+pub use simulation::Simulation;
+pub use system::DynamicalSystem;
@@ -0,0 +1,248 @@
+//! Estimating the Lyapunov exponents, the average rates at which nearby trajectories move
+//! apart or come together
+//!
+//! [`Lyapunov`] finds the largest with Benettin's method: a shadow trajectory starts a tiny
+//! distance from the reference, and every few steps the logarithm of how much the distance
+//! grew is added up and the shadow is pulled back to the starting distance along the same
+//! direction. Pulling it back keeps the separation small enough to grow exponentially, where
+//! a single pair soon saturates at the size of the attractor.
+//!
+//! [`Spectrum`] finds all three by carrying three tangent vectors along with the linearised
+//! flow instead, making them orthonormal again every few steps so that they do not all line
+//! up with the fastest growing direction.
+
+use glam::{DMat3, DVec3};
+
+use crate::{divergence::Divergence, DynamicalSystem, Simulation};
+
+/// A running estimate of the largest Lyapunov exponent
+#[derive(Debug, Clone)]
+pub struct Lyapunov {
+    divergence: Divergence,
+    /// The distance the shadow trajectory is pulled back to
+    epsilon: f64,
+    /// How many steps are taken between pulling it back
+    interval: usize,
+    /// How many steps have been taken since it was last pulled back
+    since: usize,
+    /// The sum of the logarithms of how much the separation grew over each interval
+    sum: f64,
+    /// The time the sum covers
+    elapsed: f64,
+}
+
+impl Lyapunov {
+    /// Follows `simulation` with a shadow `epsilon` along `x` from it, pulled back every
+    /// `interval` steps
+    ///
+    /// # Panics
+    /// If `epsilon` is not positive or `interval` is zero.
+    pub fn new(simulation: Simulation, epsilon: f64, interval: usize) -> Self {
+        assert!(epsilon > 0., "the shadow has to start some distance away");
+        assert!(
+            interval > 0,
+            "the shadow has to be pulled back every so often"
+        );
+
+        Self {
+            divergence: Divergence::new(simulation, DVec3::X * epsilon),
+            epsilon,
+            interval,
+            since: 0,
+            sum: 0.,
+            elapsed: 0.,
+        }
+    }
+
+    /// The trajectory the exponent is estimated along
+    pub fn reference(&self) -> &Simulation {
+        &self.divergence.reference
+    }
+
+    /// Fails with the reason either trajectory stopped, if one has
+    pub fn check(&self) -> Result<(), String> {
+        self.divergence.check()
+    }
+
+    /// Advances both trajectories by one step, pulling the shadow back when it is due
+    pub fn step(&mut self) {
+        let Divergence {
+            reference,
+            perturbed,
+        } = &mut self.divergence;
+        reference.step();
+        perturbed.step();
+        self.since += 1;
+
+        if self.since == self.interval {
+            self.since = 0;
+            let offset = perturbed.state - reference.state;
+            let distance = offset.length();
+            self.sum += (distance / self.epsilon).ln();
+            self.elapsed += self.interval as f64 * reference.dt;
+
+            perturbed.state = reference.state + offset * (self.epsilon / distance);
+            perturbed.adaptive.reset(perturbed.time, perturbed.state);
+        }
+    }
+
+    /// Takes `steps` steps and returns the estimate after them
+    pub fn run(&mut self, steps: usize) -> Option<f64> {
+        for _ in 0..steps {
+            self.step();
+        }
+        self.exponent()
+    }
+
+    /// The average rate of growth so far, or `None` before the shadow was first pulled back
+    pub fn exponent(&self) -> Option<f64> {
+        (self.elapsed > 0.).then(|| self.sum / self.elapsed)
+    }
+
+    /// The time the estimate covers
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+}
+
+/// A running estimate of every Lyapunov exponent, from the variational equations
+///
+/// The state and the tangent vectors are integrated together with classic fourth order
+/// Runge-Kutta, whichever integrator the simulation it starts from uses.
+#[derive(Debug, Clone)]
+pub struct Spectrum {
+    system: Box<dyn DynamicalSystem>,
+    state: DVec3,
+    /// The tangent vectors, one in each column, orthonormal after every interval
+    tangent: DMat3,
+    dt: f64,
+    /// How many steps are taken between making the tangent vectors orthonormal
+    interval: usize,
+    /// How many steps have been taken since they last were
+    since: usize,
+    /// The sum of the logarithms of how much each tangent vector grew over each interval
+    sums: DVec3,
+    /// The time the sums cover
+    elapsed: f64,
+}
+
+impl Spectrum {
+    /// Starts from the state of `simulation`, with its system and time step, making the
+    /// tangent vectors orthonormal every `interval` steps
+    ///
+    /// # Panics
+    /// If `interval` is zero.
+    pub fn new(simulation: Simulation, interval: usize) -> Self {
+        assert!(
+            interval > 0,
+            "the tangent vectors have to be made orthonormal every so often"
+        );
+
+        Self {
+            system: simulation.system,
+            state: simulation.state,
+            tangent: DMat3::IDENTITY,
+            dt: simulation.dt,
+            interval,
+            since: 0,
+            sums: DVec3::ZERO,
+            elapsed: 0.,
+        }
+    }
+
+    /// Advances the state and tangent vectors by one step, making the vectors orthonormal
+    /// when it is due
+    pub fn step(&mut self) {
+        (self.state, self.tangent) =
+            variational_step(&*self.system, self.state, self.tangent, self.dt);
+        self.since += 1;
+
+        if self.since == self.interval {
+            self.since = 0;
+            let (orthonormal, lengths) = gram_schmidt(self.tangent);
+            self.tangent = orthonormal;
+            self.sums += DVec3::new(lengths.x.ln(), lengths.y.ln(), lengths.z.ln());
+            self.elapsed += self.interval as f64 * self.dt;
+        }
+    }
+
+    /// Takes `steps` steps and returns the estimate after them
+    pub fn run(&mut self, steps: usize) -> Option<[f64; 3]> {
+        for _ in 0..steps {
+            self.step();
+        }
+        self.exponents()
+    }
+
+    /// The exponents from the largest down, or `None` before the tangent vectors were first
+    /// made orthonormal
+    pub fn exponents(&self) -> Option<[f64; 3]> {
+        (self.elapsed > 0.).then(|| {
+            let mut exponents = (self.sums / self.elapsed).to_array();
+            exponents.sort_by(|a, b| b.total_cmp(a));
+            exponents
+        })
+    }
+
+    /// The time the estimate covers
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+}
+
+/// One classic fourth order Runge-Kutta step of `dt` of the state `p` of `system` together
+/// with the `tangent` vectors in its columns, carried along by the linearised flow
+pub(crate) fn variational_step(
+    system: &dyn DynamicalSystem,
+    p: DVec3,
+    tangent: DMat3,
+    dt: f64,
+) -> (DVec3, DMat3) {
+    let f = |p: DVec3, tangent: DMat3| (system.derivative(p), system.jacobian(p) * tangent);
+
+    let (k1, l1) = f(p, tangent);
+    let (k2, l2) = f(p + k1 * (dt / 2.), tangent + l1 * (dt / 2.));
+    let (k3, l3) = f(p + k2 * (dt / 2.), tangent + l2 * (dt / 2.));
+    let (k4, l4) = f(p + k3 * dt, tangent + l3 * dt);
+    (
+        p + (k1 + 2. * k2 + 2. * k3 + k4) * (dt / 6.),
+        tangent + (l1 + l2 * 2. + l3 * 2. + l4) * (dt / 6.),
+    )
+}
+
+/// Makes the columns of `m` orthonormal, in order, returning them with the length each had
+/// once the earlier ones were taken out of it
+fn gram_schmidt(m: DMat3) -> (DMat3, DVec3) {
+    let mut columns = [DVec3::ZERO; 3];
+    let mut lengths = DVec3::ZERO;
+    for i in 0..3 {
+        let mut v = m.col(i);
+        for column in &columns[..i] {
+            v -= *column * column.dot(v);
+        }
+        lengths[i] = v.length();
+        columns[i] = v / lengths[i];
+    }
+    (
+        DMat3::from_cols(columns[0], columns[1], columns[2]),
+        lengths,
+    )
+}
+
+/// The Kaplan-Yorke estimate of the dimension of an attractor with the given exponents
+///
+/// With the exponents from the largest down, this is the number `j` of them whose sum is
+/// still positive, plus that sum divided by the size of the next one.
+pub fn kaplan_yorke_dimension(exponents: &[f64]) -> f64 {
+    let mut exponents = exponents.to_vec();
+    exponents.sort_by(|a, b| b.total_cmp(a));
+
+    let mut sum = 0.;
+    for (j, &exponent) in exponents.iter().enumerate() {
+        if sum + exponent < 0. {
+            return j as f64 + sum / exponent.abs();
+        }
+        sum += exponent;
+    }
+    exponents.len() as f64
+}
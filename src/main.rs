@@ -1,4 +1,7 @@
+use std::time::Duration;
+
 use bevy::{
+    math::DVec3,
     pbr::{MaterialPipeline, MaterialPipelineKey},
     prelude::*,
     reflect::TypePath,
@@ -9,76 +12,268 @@ use bevy::{
             SpecializedMeshPipelineError,
         },
     },
+    time::common_conditions::on_timer,
 };
 
 use bevy_flycam::prelude::*;
+use clap::Parser;
+use lorenz_attractor::{maxima::maximum, symbols::Wing, Simulation};
+
+mod cli;
+mod ensemble;
+mod exponents;
+mod fixed_points;
+mod headless;
+mod params;
+mod periodic_orbits;
+mod plot;
+mod replay;
+mod return_map;
+mod scene;
+mod section;
+mod separation;
+mod spectrum;
+mod trajectory;
+
+use cli::Cli;
+use ensemble::{Ensemble, EnsemblePlugin};
+use exponents::ExponentsPlugin;
+use fixed_points::FixedPointsPlugin;
+use params::{Params, ParamsPlugin};
+use periodic_orbits::PeriodicOrbitsPlugin;
+use plot::PlotPlugin;
+use replay::{Recorded, ReplayPlugin};
+use return_map::{ReturnMap, ReturnMapPlugin};
+use scene::{ColorMap, Scene};
+use section::{Poincare, SectionPlugin};
+use separation::SeparationPlugin;
+use spectrum::SpectrumPlugin;
+use trajectory::{Trajectory, TrajectoryPlugin};
 
 fn main() {
-    App::new()
+    let cli = Cli::parse();
+    let scene = cli.scene().unwrap_or_else(|err| {
+        eprintln!("error: {err}");
+        std::process::exit(2);
+    });
+
+    if let Some(command) = &cli.command {
+        if let Err(err) = headless::run(command, &scene) {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut app = App::new();
+    let mut system = scene.system();
+
+    if let Some(path) = &cli.replay {
+        let recorded = Recorded::load(path).unwrap_or_else(|err| {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        });
+        // Show what the recording was made with rather than what the scene says
+        if let Some(recorded_system) = recorded.system() {
+            system = Box::new(recorded_system);
+        }
+        app.insert_resource(recorded);
+    } else if scene.ensemble.size > 0 {
+        app.insert_resource(Ensemble {
+            size: scene.ensemble.size,
+            epsilon: scene.ensemble.epsilon,
+            center: scene.simulation(&scene.trajectories[0]).state,
+        });
+    }
+
+    app.insert_resource(scene.color_map.color_map(&system.framing()))
+        .insert_resource(Params(system))
+        .insert_resource(StepsPerFrame(scene.integration.steps_per_frame))
+        .insert_resource(scene)
         .add_plugins((DefaultPlugins, MaterialPlugin::<LineMaterial>::default()))
-        .add_plugins(NoCameraPlayerPlugin)
+        .add_plugins((
+            NoCameraPlayerPlugin,
+            TrajectoryPlugin,
+            ParamsPlugin,
+            ReplayPlugin,
+            EnsemblePlugin,
+            PlotPlugin,
+            SeparationPlugin,
+            ExponentsPlugin,
+            SectionPlugin,
+            ReturnMapPlugin,
+            FixedPointsPlugin,
+            PeriodicOrbitsPlugin,
+            SpectrumPlugin,
+        ))
         .add_systems(Startup, setup)
-        .add_systems(Update, lorenz_system)
+        .add_systems(Update, simulate)
+        .add_systems(
+            Update,
+            report_step_stats.run_if(on_timer(Duration::from_secs(1))),
+        )
         .run();
 }
 
-#[derive(Resource)]
-struct LorenzPostition {
-    translation: Vec3,
+/// The simulation behind one of the trajectories
+#[derive(Component, Deref, DerefMut)]
+struct Simulated {
+    #[deref]
+    simulation: Simulation,
+    /// Where the trajectory starts from, and starts again from when it is restarted
+    initial: DVec3,
+    /// Replaces the hue of the colour map
+    color: Option<Color>,
+    /// The wing of the loop the trajectory last reached the top of, unknown until then
+    wing: Option<Wing>,
 }
 
-fn setup(mut commands: Commands) {
+/// How many steps of the simulation are drawn every frame
+#[derive(Resource)]
+struct StepsPerFrame(u32);
+
+fn setup(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    scene: Res<Scene>,
+    params: Res<Params>,
+    color_map: Res<ColorMap>,
+    recorded: Option<Res<Recorded>>,
+    ensemble: Option<Res<Ensemble>>,
+) {
     commands.spawn((
         Camera3dBundle {
-            transform: Transform::from_xyz(-100., 0., 150.).looking_at(Vec3::ZERO, Vec3::Y),
+            transform: scene.camera.transform(&params.framing()),
             ..default()
         },
         FlyCam,
     ));
 
-    commands.insert_resource(LorenzPostition {
-        translation: Vec3::new(0.1, 0., 0.1),
-    })
-}
+    // A recording or an ensemble is drawn in place of the scene's trajectories
+    if recorded.is_some() || ensemble.is_some() {
+        return;
+    }
 
-const A: f32 = 10.;
-const B: f32 = 8. / 3.;
-const C: f32 = 28.;
-const DT: f32 = 0.001;
+    let material = materials.add(LineMaterial {
+        color: Color::WHITE,
+    });
 
-fn lorenz_system(
-    mut lorenz: ResMut<LorenzPostition>,
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<LineMaterial>>,
-) {
-    for _ in 0..50 {
-        let previous_translation = lorenz.translation;
+    let entities: Vec<_> = scene
+        .trajectories
+        .iter()
+        .map(|config| {
+            let simulation = scene.simulation(config);
+            let simulated = Simulated {
+                initial: simulation.state,
+                simulation,
+                color: config.color,
+                wing: None,
+            };
+            spawn_simulated(&mut commands, simulated, material.clone(), &color_map)
+        })
+        .collect();
 
-        let dx = A * (lorenz.translation.y - lorenz.translation.x);
+    if scene.separation.show {
+        separation::spawn_perturbed(&mut commands, &scene, entities[0], material, &color_map);
+    }
+}
 
-        let dy = lorenz.translation.x * (C - lorenz.translation.z) - lorenz.translation.y;
+/// Spawns a simulated trajectory, drawn from its initial state onwards
+fn spawn_simulated(
+    commands: &mut Commands,
+    simulated: Simulated,
+    material: Handle<LineMaterial>,
+    color_map: &ColorMap,
+) -> Entity {
+    let mut trajectory = Trajectory::new(material);
+    let translation = simulated.initial.as_vec3();
+    trajectory.push(translation, color_map.color(translation, simulated.color));
 
-        let dz = lorenz.translation.x * lorenz.translation.y - B * lorenz.translation.z;
+    commands
+        .spawn((simulated, trajectory, SpatialBundle::default()))
+        .id()
+}
 
-        lorenz.translation.x += dx * DT;
+fn simulate(
+    steps: Res<StepsPerFrame>,
+    color_map: Res<ColorMap>,
+    mut poincare: Option<ResMut<Poincare>>,
+    mut return_map: Option<ResMut<ReturnMap>>,
+    mut trajectories: Query<(Entity, &mut Simulated, &mut Trajectory)>,
+) {
+    for (entity, mut simulated, mut trajectory) in &mut trajectories {
+        for _ in 0..steps.0 {
+            if simulated.failure.is_some() {
+                break;
+            }
+            let from = (simulated.time, simulated.state);
+            // The simulation runs in double precision, only what is drawn is narrowed down
+            let translation = simulated.step().as_vec3();
+            if let Some(failure) = &simulated.failure {
+                warn!("a trajectory stopped: {failure}");
+                break;
+            }
+            let to = (simulated.time, simulated.state);
 
-        lorenz.translation.y += dy * DT;
+            // A loop takes the colour of its wing from its top on
+            if color_map.wings.is_some() {
+                if let Some(top) = maximum(&*simulated.system, from, to) {
+                    simulated.wing = Some(Wing::of(top.point));
+                }
+            }
+            let base = simulated
+                .wing
+                .and_then(|wing| color_map.wing_color(wing))
+                .or(simulated.color);
+            let color = color_map.color(translation, base);
 
-        lorenz.translation.z += dz * DT;
+            // Only a crossing or a maximum changes what is drawn, the many steps without
+            // either do not
+            let system = &*simulated.system;
+            if let Some(poincare) = &mut poincare {
+                if poincare
+                    .bypass_change_detection()
+                    .record(system, from, to, color)
+                {
+                    poincare.set_changed();
+                }
+            }
+            if let Some(return_map) = &mut return_map {
+                if return_map.bypass_change_detection().record(
+                    entity,
+                    system,
+                    from,
+                    to,
+                    simulated.color,
+                ) {
+                    return_map.set_changed();
+                }
+            }
+            trajectory.push(translation, color);
+        }
+    }
+}
 
-        let h = map_range((-13., 13.), (25., 35.), lorenz.translation.x);
-        let l = map_range((-28., 28.), (0.3, 0.7), lorenz.translation.y);
+/// Clears the trajectory and starts it again from its initial state
+fn restart(simulated: &mut Simulated, trajectory: &mut Trajectory, color_map: &ColorMap) {
+    let initial = simulated.initial;
+    simulated.restart(initial);
+    simulated.wing = None;
+    trajectory.clear();
 
-        commands.spawn(MaterialMeshBundle {
-            mesh: meshes.add(Mesh::from(LineStrip {
-                points: vec![previous_translation, lorenz.translation],
-            })),
-            material: materials.add(LineMaterial {
-                color: Color::hsla(h, 0.8, l, 0.5),
-            }),
-            ..default()
-        });
+    let translation = initial.as_vec3();
+    trajectory.push(translation, color_map.color(translation, simulated.color));
+}
+
+fn report_step_stats(trajectories: Query<&Simulated>) {
+    for (index, simulated) in trajectories.iter().enumerate() {
+        if simulated.integrator.is_adaptive() {
+            let stats = simulated.adaptive.stats;
+            info!(
+                "trajectory {index}, {}: {} steps accepted, {} rejected",
+                simulated.integrator, stats.accepted, stats.rejected
+            );
+        }
     }
 }
 
@@ -98,10 +293,10 @@ for num in nums {
 }
 ```
 */
-
-fn map_range<T: Copy>(from_range: (T, T), to_range: (T, T), s: T) -> T
+fn map_range<T>(from_range: (T, T), to_range: (T, T), s: T) -> T
 where
-    T: std::ops::Add<T, Output = T>
+    T: Copy
+        + std::ops::Add<T, Output = T>
         + std::ops::Sub<T, Output = T>
         + std::ops::Mul<T, Output = T>
         + std::ops::Div<T, Output = T>,
@@ -136,14 +331,27 @@ impl Material for LineMaterial {
 #[derive(Debug, Clone)]
 pub struct LineStrip {
     pub points: Vec<Vec3>,
+    /// An optional colour for each point, blended along every segment by [`LineMaterial`]
+    pub colors: Option<Vec<Color>>,
 }
 
 impl From<LineStrip> for Mesh {
     fn from(line: LineStrip) -> Self {
         // This tells wgpu that the positions are a list of points
         // where a line will be drawn between each consecutive point
-        Mesh::new(PrimitiveTopology::LineStrip)
+        let mesh = Mesh::new(PrimitiveTopology::LineStrip)
             // Add the point positions as an attribute
-            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, line.points)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, line.points);
+
+        match line.colors {
+            Some(colors) => {
+                let colors: Vec<[f32; 4]> = colors
+                    .into_iter()
+                    .map(|color| color.as_linear_rgba_f32())
+                    .collect();
+                mesh.with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+            }
+            None => mesh,
+        }
     }
 }
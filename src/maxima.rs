@@ -0,0 +1,61 @@
+//! The local maxima of `z` along a trajectory, and the return map they make
+//!
+//! Lorenz noticed in 1963 that each maximum of `z` on his attractor all but decides the next:
+//! plotting every maximum against the one before draws a thin cusp-shaped curve, turning the
+//! flow into a one dimensional map. A maximum is found between two steps where `dz/dt` goes
+//! from positive to negative, and pinned down within the step like a Poincaré crossing.
+
+use glam::DVec3;
+
+use crate::{
+    poincare::{interpolant, sign_change},
+    DynamicalSystem, Simulation,
+};
+
+/// A local maximum of `z`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Maximum {
+    pub time: f64,
+    /// The state at the maximum, its height is `point.z`
+    pub point: DVec3,
+}
+
+/// The maximum of `z` reached during the step of `system` from the `(time, state)` pair
+/// `from` to `to`, if there is one
+pub fn maximum(
+    system: &dyn DynamicalSystem,
+    from: (f64, DVec3),
+    to: (f64, DVec3),
+) -> Option<Maximum> {
+    let rising = |p| system.derivative(p).z;
+    if !(rising(from.1) > 0. && rising(to.1) <= 0.) {
+        return None;
+    }
+
+    let at = interpolant(system, from, to);
+    let s = sign_change(|s| rising(at(s)), false);
+    Some(Maximum {
+        time: from.0 + s * (to.0 - from.0),
+        point: at(s),
+    })
+}
+
+/// The maxima over the next `steps` steps of `simulation`
+pub fn maxima(simulation: &mut Simulation, steps: usize) -> impl Iterator<Item = Maximum> + '_ {
+    (0..steps).filter_map(move |_| {
+        let from = (simulation.time, simulation.state);
+        simulation.step();
+        maximum(
+            &*simulation.system,
+            from,
+            (simulation.time, simulation.state),
+        )
+    })
+}
+
+/// Each maximum paired with the one after it, the points `(z_n, z_n+1)` of the return map
+pub fn return_map(maxima: &[Maximum]) -> impl Iterator<Item = (f64, f64)> + '_ {
+    maxima
+        .windows(2)
+        .map(|pair| (pair[0].point.z, pair[1].point.z))
+}
@@ -0,0 +1,326 @@
+//! Unstable periodic orbits, the closed loops a chaotic attractor is built around
+//!
+//! A chaotic trajectory keeps coming back close to where it has been before, shadowing for a
+//! while one of the infinitely many periodic orbits embedded in the attractor, all of them
+//! unstable. The search looks for such close returns between the crossings of a Poincaré
+//! section, and refines each into an exact orbit with Newton's method: the start on the
+//! plane and the period are corrected together until the flow over one period brings the
+//! start back onto itself.
+//!
+//! Each orbit is named by the wings it goes around between crossings, `L` for a crossing with
+//! a negative `x` and `R` for a positive one, rotated to come first in alphabetical order, so
+//! `LR` is the simplest orbit of the Lorenz attractor, once around each wing.
+
+use std::collections::HashMap;
+
+use glam::{DMat3, DVec3};
+
+use crate::{
+    equilibria::eigenvalues,
+    lyapunov::variational_step,
+    poincare::{Crossing, Section},
+    symbols::Wing,
+    DynamicalSystem, Integrator, Simulation,
+};
+
+/// How many Newton iterations a close return is given to converge into an orbit
+const ITERATIONS: usize = 40;
+
+/// A periodic orbit of a system
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeriodicOrbit {
+    /// The wing, `L` or `R`, of each crossing of the section over one period
+    pub sequence: String,
+    /// The time it takes to go around once
+    pub period: f64,
+    /// A point of the orbit on the plane of the section
+    pub start: DVec3,
+    /// How much the orbit stretches a small offset from it on every turn, the largest Floquet
+    /// multiplier in size
+    pub multiplier: f64,
+}
+
+impl PeriodicOrbit {
+    /// How many times the orbit crosses the section on every turn
+    pub fn crossings(&self) -> usize {
+        self.sequence.len()
+    }
+
+    /// How fast nearby trajectories move away from the orbit, its Floquet exponent
+    pub fn exponent(&self) -> f64 {
+        self.multiplier.ln() / self.period
+    }
+
+    /// The orbit sampled `steps` times evenly over one period, from its start and closed by
+    /// coming back to it at the end
+    pub fn points(&self, system: &dyn DynamicalSystem, steps: usize) -> Vec<DVec3> {
+        let dt = self.period / steps.max(1) as f64;
+        let mut p = self.start;
+        let mut points = vec![p];
+        for _ in 0..steps.max(1) {
+            p = advance(system, p, dt);
+            points.push(p);
+        }
+        points
+    }
+}
+
+/// A search for the periodic orbits a trajectory comes close to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Search {
+    /// The section the returns are looked for on
+    pub section: Section,
+    /// The most crossings of the section an orbit may take to close
+    pub max_crossings: usize,
+    /// How many steps the trajectory takes before its crossings are looked at
+    pub transient: usize,
+    /// How many steps the trajectory is followed for
+    pub steps: usize,
+    /// How close a return has to be to be refined, as a fraction of how far apart the
+    /// crossings spread over the plane
+    pub closeness: f64,
+    /// The most close returns refined for each sequence of wings they went through, the
+    /// closest first
+    pub attempts: usize,
+}
+
+impl Search {
+    /// The distinct orbits found from the close returns of `simulation`, by period and then by
+    /// sequence, failing when the simulation does
+    pub fn run(&self, mut simulation: Simulation) -> Result<Vec<PeriodicOrbit>, String> {
+        for _ in 0..self.transient {
+            simulation.step();
+        }
+        let crossings: Vec<Crossing> = self
+            .section
+            .crossings(&mut simulation, self.steps)
+            .collect();
+        simulation.check()?;
+        let system = &*simulation.system;
+        let step = simulation.dt;
+
+        let (low, high) = crossings.iter().fold(
+            (DVec3::splat(f64::INFINITY), DVec3::splat(f64::NEG_INFINITY)),
+            |(low, high), crossing| (low.min(crossing.point), high.max(crossing.point)),
+        );
+        let close = self.closeness * low.distance(high);
+
+        let mut orbits: Vec<PeriodicOrbit> = Vec::new();
+        for n in 1..=self.max_crossings {
+            let mut returns: Vec<_> = crossings
+                .windows(n + 1)
+                .filter(|window| window[0].point.distance(window[n].point) < close)
+                .collect();
+            returns.sort_by(|a, b| {
+                let distance = |window: &[Crossing]| window[0].point.distance(window[n].point);
+                distance(a).total_cmp(&distance(b))
+            });
+
+            // The closest returns to each orbit are likely to shadow the same one, so each
+            // sequence the trajectory went through only gets so many attempts
+            let mut tried: HashMap<String, usize> = HashMap::new();
+            for window in returns {
+                let wings: String = window[..n]
+                    .iter()
+                    .map(|crossing| Wing::of(crossing.point).symbol())
+                    .collect();
+                let Some(shadowed) = canonical(&wings) else {
+                    continue;
+                };
+                let attempts = tried.entry(shadowed.clone()).or_default();
+                if *attempts == self.attempts
+                    || orbits.iter().any(|orbit| orbit.sequence == shadowed)
+                {
+                    continue;
+                }
+                *attempts += 1;
+
+                let (from, to) = (window[0], window[n]);
+                let Some((start, period)) =
+                    self.refine(system, from.point, to.time - from.time, step)
+                else {
+                    continue;
+                };
+                // An equilibrium on the plane comes back onto itself after any time at all
+                if system.derivative(start).length() <= 1e-6 * start.length().max(1.) {
+                    continue;
+                }
+                let Some(orbit) = self.orbit(system, start, period, step) else {
+                    continue;
+                };
+                if orbit.crossings() == n
+                    && orbits.iter().all(|found| found.sequence != orbit.sequence)
+                {
+                    orbits.push(orbit);
+                }
+            }
+        }
+
+        // Mirror images of each other have the same period up to the accuracy it is found to,
+        // so the periods are rounded before they are compared and the sequence breaks the tie
+        let period = |orbit: &PeriodicOrbit| (orbit.period * 1e6).round();
+        orbits.sort_by(|a, b| {
+            period(a)
+                .total_cmp(&period(b))
+                .then_with(|| a.sequence.cmp(&b.sequence))
+        });
+        Ok(orbits)
+    }
+
+    /// Corrects `start` and `period` with Newton's method until the flow brings the start
+    /// back onto itself, keeping the start on the plane
+    fn refine(
+        &self,
+        system: &dyn DynamicalSystem,
+        mut start: DVec3,
+        mut period: f64,
+        step: f64,
+    ) -> Option<(DVec3, f64)> {
+        let plane = self.section.plane;
+        for _ in 0..ITERATIONS {
+            let (end, monodromy) = flow(system, start, period, step);
+            let miss = end - start;
+            if miss.length() <= 1e-9 * start.length().max(1.) {
+                return Some((start, period));
+            }
+
+            // The miss and the distance from the plane both have to vanish: four equations
+            // in the three coordinates of the start and the period
+            let velocity = system.derivative(end);
+            let jacobian = [
+                [
+                    monodromy.x_axis.x - 1.,
+                    monodromy.y_axis.x,
+                    monodromy.z_axis.x,
+                    velocity.x,
+                ],
+                [
+                    monodromy.x_axis.y,
+                    monodromy.y_axis.y - 1.,
+                    monodromy.z_axis.y,
+                    velocity.y,
+                ],
+                [
+                    monodromy.x_axis.z,
+                    monodromy.y_axis.z,
+                    monodromy.z_axis.z - 1.,
+                    velocity.z,
+                ],
+                [plane.normal.x, plane.normal.y, plane.normal.z, 0.],
+            ];
+            let residual = [-miss.x, -miss.y, -miss.z, -plane.distance(start)];
+            let [dx, dy, dz, dt] = solve(jacobian, residual)?;
+
+            start += DVec3::new(dx, dy, dz);
+            period += dt;
+            if !start.is_finite() || period.is_nan() || period <= 0. {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// The orbit through `start` with the given period, found by following it around once
+    /// from a little past the start, so that the crossing at the start is counted only once
+    fn orbit(
+        &self,
+        system: &dyn DynamicalSystem,
+        start: DVec3,
+        period: f64,
+        step: f64,
+    ) -> Option<PeriodicOrbit> {
+        let steps = (period / step).ceil().max(1.) as usize;
+        let dt = period / steps as f64;
+        let offset = steps / 7 + 1;
+
+        let mut p = start;
+        for _ in 0..offset {
+            p = advance(system, p, dt);
+        }
+        let mut sequence = String::new();
+        let mut time = 0.;
+        for _ in 0..steps {
+            let from = (time, p);
+            p = advance(system, p, dt);
+            time += dt;
+            if let Some(crossing) = self.section.crossing(system, from, (time, p)) {
+                sequence.push(Wing::of(crossing.point).symbol());
+            }
+        }
+        // A loop going around a shorter one more than once is that orbit again
+        let sequence = canonical(&sequence)?;
+
+        let (_, monodromy) = flow(system, start, period, step);
+        let multiplier = eigenvalues(monodromy)
+            .iter()
+            .map(|eigenvalue| eigenvalue.re.hypot(eigenvalue.im))
+            .fold(0., f64::max);
+        Some(PeriodicOrbit {
+            sequence,
+            period,
+            start,
+            multiplier,
+        })
+    }
+}
+
+/// One classic fourth order Runge-Kutta step, the scheme the orbits are refined with
+fn advance(system: &dyn DynamicalSystem, p: DVec3, dt: f64) -> DVec3 {
+    Integrator::RungeKutta4.step(|p| system.derivative(p), p, dt)
+}
+
+/// Where the flow of `system` takes `start` after `period`, and how it carries small offsets
+/// from it along, in steps no longer than `step`
+fn flow(system: &dyn DynamicalSystem, start: DVec3, period: f64, step: f64) -> (DVec3, DMat3) {
+    let steps = (period / step).ceil().max(1.) as usize;
+    let dt = period / steps as f64;
+    let (mut p, mut tangent) = (start, DMat3::IDENTITY);
+    for _ in 0..steps {
+        (p, tangent) = variational_step(system, p, tangent, dt);
+    }
+    (p, tangent)
+}
+
+/// The rotation of `sequence` that comes first in alphabetical order, the name of the orbit
+/// it goes around, or `None` when it is empty or a shorter sequence repeated
+pub fn canonical(sequence: &str) -> Option<String> {
+    let n = sequence.len();
+    if n == 0
+        || (1..n).any(|shift| n.is_multiple_of(shift) && sequence[shift..] == sequence[..n - shift])
+    {
+        return None;
+    }
+    (0..n)
+        .map(|shift| format!("{}{}", &sequence[shift..], &sequence[..shift]))
+        .min()
+}
+
+/// Solves `a x = b` by Gaussian elimination with partial pivoting, `None` when `a` is
+/// singular
+fn solve(mut a: [[f64; 4]; 4], mut b: [f64; 4]) -> Option<[f64; 4]> {
+    for column in 0..4 {
+        let pivot =
+            (column..4).max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))?;
+        if a[pivot][column] == 0. {
+            return None;
+        }
+        a.swap(column, pivot);
+        b.swap(column, pivot);
+
+        for row in column + 1..4 {
+            let factor = a[row][column] / a[column][column];
+            let pivot_row = a[column];
+            for (value, pivot) in a[row].iter_mut().zip(pivot_row).skip(column) {
+                *value -= factor * pivot;
+            }
+            b[row] -= factor * b[column];
+        }
+    }
+
+    let mut x = [0.; 4];
+    for row in (0..4).rev() {
+        let rest: f64 = (row + 1..4).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - rest) / a[row][row];
+    }
+    Some(x)
+}
@@ -0,0 +1,175 @@
+//! Changing the parameters of the system while the simulation is running
+//!
+//! Left and right pick a parameter, up and down change it by a step that suits its starting
+//! size (ten times as much while control is held). R toggles whether the trajectory is
+//! cleared and restarted from its initial state whenever a parameter changes.
+
+use bevy::prelude::*;
+use lorenz_attractor::{Attractor, DynamicalSystem};
+
+use crate::{restart, scene::ColorMap, Simulated, Trajectory};
+
+pub struct ParamsPlugin;
+
+impl Plugin for ParamsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Params>()
+            .init_resource::<ParamsEditor>()
+            .add_systems(Startup, spawn_panel)
+            .add_systems(
+                Update,
+                (
+                    edit_params,
+                    apply_params.run_if(resource_changed::<Params>()),
+                    update_panel.run_if(
+                        resource_changed::<Params>().or_else(resource_changed::<ParamsEditor>()),
+                    ),
+                )
+                    .chain()
+                    .before(crate::simulate),
+            );
+    }
+}
+
+/// The system and parameters the simulation should be using, any change to the parameters is
+/// applied to every [`Simulated`]
+#[derive(Resource, Deref, DerefMut)]
+pub struct Params(pub Box<dyn DynamicalSystem>);
+
+impl Default for Params {
+    fn default() -> Self {
+        Self(Box::new(Attractor::default()))
+    }
+}
+
+#[derive(Resource)]
+struct ParamsEditor {
+    /// Index into the parameters of the system
+    selected: usize,
+    restart_on_change: bool,
+    /// How much a single key press changes each parameter by
+    steps: Vec<f64>,
+}
+
+impl FromWorld for ParamsEditor {
+    fn from_world(world: &mut World) -> Self {
+        let params = world.resource::<Params>();
+        Self {
+            selected: 0,
+            restart_on_change: true,
+            steps: params.params().iter().map(|&value| step(value)).collect(),
+        }
+    }
+}
+
+/// The power of ten a tenth or less of `value`, so that parameters of any size can be edited
+fn step(value: f64) -> f64 {
+    if value == 0. {
+        0.1
+    } else {
+        10f64.powf(value.abs().log10().floor() - 1.)
+    }
+}
+
+#[derive(Component)]
+struct ParamsPanel;
+
+fn spawn_panel(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.),
+            left: Val::Px(10.),
+            ..default()
+        }),
+        ParamsPanel,
+    ));
+}
+
+fn edit_params(
+    keys: Res<Input<KeyCode>>,
+    mut editor: ResMut<ParamsEditor>,
+    mut params: ResMut<Params>,
+) {
+    let count = params.params().len();
+    if count == 0 {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Right) {
+        editor.selected = (editor.selected + 1) % count;
+    }
+    if keys.just_pressed(KeyCode::Left) {
+        editor.selected = (editor.selected + count - 1) % count;
+    }
+    if keys.just_pressed(KeyCode::R) {
+        editor.restart_on_change = !editor.restart_on_change;
+    }
+
+    let mut change = 0.;
+    if keys.just_pressed(KeyCode::Up) {
+        change += 1.;
+    }
+    if keys.just_pressed(KeyCode::Down) {
+        change -= 1.;
+    }
+
+    if change != 0. {
+        if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+            change *= 10.;
+        }
+
+        let selected = editor.selected;
+        params.params_mut()[selected] += change * editor.steps[selected];
+    }
+}
+
+fn apply_params(
+    params: Res<Params>,
+    editor: Res<ParamsEditor>,
+    color_map: Res<ColorMap>,
+    mut trajectories: Query<(&mut Simulated, &mut Trajectory)>,
+) {
+    for (mut simulated, mut trajectory) in &mut trajectories {
+        simulated.set_params(params.params());
+
+        if editor.restart_on_change {
+            restart(&mut simulated, &mut trajectory, &color_map);
+        }
+    }
+}
+
+fn update_panel(
+    params: Res<Params>,
+    editor: Res<ParamsEditor>,
+    mut panels: Query<&mut Text, With<ParamsPanel>>,
+) {
+    let names = params.param_names();
+    let width = names.iter().map(|name| name.len()).max().unwrap_or(0);
+
+    let mut text = format!("{}\n", params.name());
+    for (index, (name, value)) in names.iter().zip(params.params()).enumerate() {
+        let marker = if index == editor.selected { '>' } else { ' ' };
+        text.push_str(&format!("{marker} {name:<width$} {value:8.3}\n"));
+    }
+    text.push_str(&format!(
+        "restart on change: {} (R)",
+        if editor.restart_on_change {
+            "on"
+        } else {
+            "off"
+        }
+    ));
+
+    for mut panel in &mut panels {
+        panel.sections[0].value = text.clone();
+    }
+}
@@ -0,0 +1,241 @@
+//! The unstable periodic orbits of the attractor, one of them drawn at a time as a bright
+//! closed loop over the trajectories
+//!
+//! The orbits are searched for in the background along a trajectory of their own, which is not
+//! drawn, and again whenever a parameter changes. They are listed by period, and O steps
+//! through them and past the last one hides the loop.
+
+use bevy::{
+    prelude::*,
+    render::{mesh::PrimitiveTopology, view::NoFrustumCulling},
+    tasks::{block_on, AsyncComputeTaskPool, Task},
+};
+use lorenz_attractor::orbits::{self, PeriodicOrbit, Search};
+
+use crate::{params::Params, scene::Scene, LineMaterial, LineStrip};
+
+/// How many steps the trajectory the orbits are searched along takes before and while looking
+/// for close returns
+const TRANSIENT: usize = 10_000;
+const STEPS: usize = 1_000_000;
+
+/// How many points each orbit is drawn with
+const POINTS: usize = 4000;
+
+pub struct PeriodicOrbitsPlugin;
+
+impl Plugin for PeriodicOrbitsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_orbit).add_systems(
+            Update,
+            (
+                search_orbits.run_if(
+                    resource_exists::<PeriodicOrbits>().and_then(resource_changed::<Params>()),
+                ),
+                receive_orbits.run_if(resource_exists::<PeriodicOrbits>()),
+                select_orbit.run_if(resource_exists::<PeriodicOrbits>()),
+                draw_orbit.run_if(resource_exists_and_changed::<PeriodicOrbits>()),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The orbits found for the current parameters, and the one drawn
+#[derive(Resource)]
+struct PeriodicOrbits {
+    found: Vec<PeriodicOrbit>,
+    /// Index into `found`
+    selected: Option<usize>,
+    /// The search going on in the background, if the parameters changed since the last one
+    search: Option<Task<Result<Vec<PeriodicOrbit>, String>>>,
+    /// The sequence of the orbit to draw once the search is done
+    wanted: Option<String>,
+}
+
+/// The loop of the orbit drawn
+#[derive(Component)]
+struct OrbitLoop(Handle<Mesh>);
+
+#[derive(Component)]
+struct OrbitPanel;
+
+fn spawn_orbit(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut lines: ResMut<Assets<LineMaterial>>,
+    scene: Res<Scene>,
+) {
+    if !scene.orbits.show {
+        return;
+    }
+
+    let mesh = meshes.add(Mesh::new(PrimitiveTopology::LineStrip));
+    commands.spawn((
+        MaterialMeshBundle {
+            mesh: mesh.clone(),
+            material: lines.add(LineMaterial {
+                color: Color::rgb(1., 0.95, 0.4),
+            }),
+            ..default()
+        },
+        // The bounds change with every orbit drawn
+        NoFrustumCulling,
+        OrbitLoop(mesh),
+    ));
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.),
+            left: Val::Px(10.),
+            ..default()
+        }),
+        OrbitPanel,
+    ));
+
+    commands.insert_resource(PeriodicOrbits {
+        found: Vec::new(),
+        selected: None,
+        search: None,
+        wanted: None,
+    });
+}
+
+fn search_orbits(scene: Res<Scene>, params: Res<Params>, mut orbits: ResMut<PeriodicOrbits>) {
+    // Keep showing the same orbit if there still is one, otherwise the one the scene picks
+    let wanted = orbits
+        .selected
+        .map(|index| orbits.found[index].sequence.clone())
+        .or_else(|| orbits.wanted.take())
+        .or_else(|| {
+            let selected = scene.orbits.selected.as_deref()?;
+            orbits::canonical(selected)
+        });
+    // The orbits found belong to the old parameters, and a search still going on for them is
+    // dropped, which cancels it
+    orbits.found.clear();
+    orbits.selected = None;
+    orbits.search = None;
+    orbits.wanted = wanted;
+
+    let mut simulation = scene.simulation(&scene.trajectories[0]);
+    simulation.system = params.0.clone();
+    let section = match scene.poincare.section(&*params.0) {
+        Ok(section) => section,
+        Err(err) => {
+            warn!("periodic orbits: {err}");
+            return;
+        }
+    };
+    let search = Search {
+        section,
+        max_crossings: scene.orbits.max_crossings,
+        transient: TRANSIENT,
+        steps: STEPS,
+        closeness: 0.02,
+        attempts: 5,
+    };
+
+    orbits.search = Some(AsyncComputeTaskPool::get().spawn(async move { search.run(simulation) }));
+}
+
+fn receive_orbits(mut orbits: ResMut<PeriodicOrbits>) {
+    // Checking on the search is not a change, only its result is
+    if !orbits.search.as_ref().is_some_and(Task::is_finished) {
+        return;
+    }
+    let Some(search) = orbits.search.take() else {
+        return;
+    };
+
+    orbits.found = block_on(search).unwrap_or_else(|err| {
+        warn!("periodic orbits: {err}");
+        Vec::new()
+    });
+    orbits.selected = match orbits.wanted.take() {
+        Some(sequence) => {
+            let index = orbits
+                .found
+                .iter()
+                .position(|orbit| orbit.sequence == sequence);
+            if index.is_none() {
+                warn!("periodic orbits: no {sequence} orbit was found");
+            }
+            index
+        }
+        None => (!orbits.found.is_empty()).then_some(0),
+    };
+}
+
+fn select_orbit(keys: Res<Input<KeyCode>>, mut orbits: ResMut<PeriodicOrbits>) {
+    if keys.just_pressed(KeyCode::O) {
+        let count = orbits.found.len();
+        orbits.selected = match orbits.selected {
+            None if count > 0 => Some(0),
+            Some(index) if index + 1 < count => Some(index + 1),
+            _ => None,
+        };
+    }
+}
+
+fn draw_orbit(
+    orbits: Res<PeriodicOrbits>,
+    params: Res<Params>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    loops: Query<&OrbitLoop>,
+    mut panels: Query<&mut Text, With<OrbitPanel>>,
+) {
+    let selected = orbits.selected.map(|index| &orbits.found[index]);
+
+    let points = selected.map_or(Vec::new(), |orbit| {
+        orbit
+            .points(&*params.0, POINTS)
+            .into_iter()
+            .map(|p| p.as_vec3())
+            .collect()
+    });
+    for OrbitLoop(mesh) in &loops {
+        meshes.insert(
+            mesh.clone(),
+            Mesh::from(LineStrip {
+                points: points.clone(),
+                colors: None,
+            }),
+        );
+    }
+
+    // Every orbit found with its period, by period, and the one drawn marked
+    let mut text = match selected {
+        Some(orbit) => format!(
+            "periodic orbit {} of {}, Floquet exponent {:.3} (O)",
+            orbits.selected.unwrap_or(0) + 1,
+            orbits.found.len(),
+            orbit.exponent()
+        ),
+        None if orbits.search.is_some() => "periodic orbits: searching".into(),
+        None if orbits.found.is_empty() => "periodic orbits: none found".into(),
+        None => format!(
+            "periodic orbits: {} found, none drawn (O)",
+            orbits.found.len()
+        ),
+    };
+    for (index, orbit) in orbits.found.iter().enumerate() {
+        let marker = if orbits.selected == Some(index) {
+            '>'
+        } else {
+            ' '
+        };
+        text += &format!("\n{marker} {:.4}  {}", orbit.period, orbit.sequence);
+    }
+    for mut panel in &mut panels {
+        panel.sections[0].value = text.clone();
+    }
+}
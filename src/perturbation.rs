@@ -0,0 +1,18 @@
+//! Starting points a tiny distance apart, to follow how quickly nearby trajectories part ways
+
+use std::f64::consts::PI;
+
+use glam::DVec3;
+
+/// `size` offsets of length `epsilon`, spread evenly over the sphere along a Fibonacci spiral
+/// so that no two members of an ensemble start off in the same direction
+pub fn perturbations(size: usize, epsilon: f64) -> impl Iterator<Item = DVec3> {
+    let golden_angle = PI * (3. - 5f64.sqrt());
+
+    (0..size).map(move |i| {
+        let z = 1. - (2 * i + 1) as f64 / size as f64;
+        let radius = (1. - z * z).sqrt();
+        let angle = golden_angle * i as f64;
+        epsilon * DVec3::new(radius * angle.cos(), radius * angle.sin(), z)
+    })
+}
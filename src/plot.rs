@@ -0,0 +1,337 @@
+//! Two dimensional charts drawn over the bottom right corner of the window
+//!
+//! Every [`Plot`] is one mesh of coloured line segments on a translucent panel, seen by an
+//! orthographic camera laid over the 3D view that measures in logical pixels. The title,
+//! notes and ranges of the axes are written at the top of the panel. Several plots are stacked
+//! upwards in the order they were spawned.
+
+use bevy::{
+    core_pipeline::{clear_color::ClearColorConfig, tonemapping::Tonemapping},
+    prelude::*,
+    render::{
+        camera::ScalingMode,
+        mesh::PrimitiveTopology,
+        view::{NoFrustumCulling, RenderLayers},
+    },
+    window::{PrimaryWindow, WindowResized},
+};
+
+use crate::LineMaterial;
+
+/// The render layer only the overlay camera sees
+const LAYER: u8 = 1;
+
+/// The size of each panel in logical pixels
+const PANEL: Vec2 = Vec2::new(380., 260.);
+/// The room between a panel and the edges of the window
+const MARGIN: f32 = 10.;
+/// The room between the edges of a panel and what is drawn on it
+const PADDING: f32 = 8.;
+/// Half the width of the cross at each point of a scattered series
+const CROSS: f32 = 2.;
+/// The height of the text at the top of a panel
+const HEADER: f32 = 60.;
+
+pub struct PlotPlugin;
+
+impl Plugin for PlotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_overlay).add_systems(
+            PostUpdate,
+            (attach_plots, apply_deferred, draw_plots).chain(),
+        );
+    }
+}
+
+/// A chart of one or more series of points
+#[derive(Component, Default)]
+pub struct Plot {
+    pub title: String,
+    /// Written below the title, such as a value fitted to the points
+    pub notes: String,
+    pub x_label: String,
+    pub y_label: String,
+    /// The range of `x` shown, fitted to the points when `None`
+    pub x: Option<[f32; 2]>,
+    /// The range of `y` shown, fitted to the points when `None`
+    pub y: Option<[f32; 2]>,
+    pub series: Vec<Series>,
+}
+
+/// Points joined up by a line, or scattered
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub points: Vec<Vec2>,
+    pub color: Color,
+    /// Draws a small cross at each point instead of joining them up
+    pub scatter: bool,
+}
+
+/// The entities and mesh a plot is drawn with
+#[derive(Component)]
+struct PlotParts {
+    mesh: Handle<Mesh>,
+    background: Entity,
+    label: Entity,
+    /// How many panels up from the bottom of the stack it is drawn at
+    slot: usize,
+}
+
+#[derive(Resource)]
+struct PlotMaterials {
+    lines: Handle<LineMaterial>,
+    background: Handle<StandardMaterial>,
+    panel: Handle<Mesh>,
+}
+
+fn setup_overlay(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut lines: ResMut<Assets<LineMaterial>>,
+    mut backgrounds: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        Camera3dBundle {
+            camera: Camera {
+                // Drawn after the 3D view, on top of it
+                order: 1,
+                ..default()
+            },
+            camera_3d: Camera3d {
+                clear_color: ClearColorConfig::None,
+                ..default()
+            },
+            projection: OrthographicProjection {
+                scaling_mode: ScalingMode::WindowSize(1.),
+                ..default()
+            }
+            .into(),
+            // The 3D view has already been tonemapped
+            tonemapping: Tonemapping::None,
+            transform: Transform::from_xyz(0., 0., 100.),
+            ..default()
+        },
+        // The UI is already drawn by the 3D view's camera
+        UiCameraConfig { show_ui: false },
+        RenderLayers::layer(LAYER),
+    ));
+
+    commands.insert_resource(PlotMaterials {
+        lines: lines.add(LineMaterial {
+            color: Color::WHITE,
+        }),
+        background: backgrounds.add(StandardMaterial {
+            base_color: Color::rgba(0., 0., 0., 0.6),
+            unlit: true,
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        }),
+        panel: meshes.add(shape::Quad::new(PANEL).into()),
+    });
+}
+
+/// Gives every new plot the entities it is drawn with, the mesh is filled in by [`draw_plots`]
+fn attach_plots(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    materials: Res<PlotMaterials>,
+    plots: Query<Entity, Added<Plot>>,
+) {
+    for entity in &plots {
+        let background = commands
+            .spawn((
+                MaterialMeshBundle {
+                    mesh: materials.panel.clone(),
+                    material: materials.background.clone(),
+                    ..default()
+                },
+                RenderLayers::layer(LAYER),
+            ))
+            .id();
+        let label = commands
+            .spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ))
+            .id();
+        let mesh = meshes.add(Mesh::new(PrimitiveTopology::LineList));
+
+        commands.entity(entity).insert((
+            MaterialMeshBundle {
+                mesh: mesh.clone(),
+                material: materials.lines.clone(),
+                ..default()
+            },
+            RenderLayers::layer(LAYER),
+            // The bounds change with the points, the whole plot is always in view anyway
+            NoFrustumCulling,
+            PlotParts {
+                mesh,
+                background,
+                label,
+                slot: 0,
+            },
+        ));
+    }
+}
+
+fn draw_plots(
+    mut meshes: ResMut<Assets<Mesh>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut resized: EventReader<WindowResized>,
+    mut plots: Query<(Entity, Ref<Plot>, &mut PlotParts)>,
+    mut labels: Query<(&mut Text, &mut Style)>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+    let resized = resized.read().count() > 0;
+
+    // Entities spawned later have higher indices, so they go further up the stack
+    let mut order: Vec<Entity> = plots.iter().map(|(entity, ..)| entity).collect();
+    order.sort();
+
+    for (entity, plot, mut parts) in &mut plots {
+        let slot = order.binary_search(&entity).unwrap_or_default();
+        if !(plot.is_changed() || resized || parts.slot != slot) {
+            continue;
+        }
+        parts.slot = slot;
+
+        // The panel's top left corner in window coordinates, which run down from the top left
+        let top_left = window_size - MARGIN - PANEL - Vec2::Y * (PANEL.y + MARGIN) * slot as f32;
+        // The overlay camera's world coordinates run up from the middle of the window
+        let to_world = |p: Vec2| Vec2::new(p.x - window_size.x / 2., window_size.y / 2. - p.y);
+        let area = Rect::from_corners(
+            to_world(top_left + Vec2::new(PADDING, HEADER)),
+            to_world(top_left + PANEL - PADDING),
+        );
+
+        let x = plot.x.unwrap_or_else(|| {
+            fit_range(
+                plot.series
+                    .iter()
+                    .flat_map(|s| s.points.iter().map(|p| p.x)),
+            )
+        });
+        let y = plot.y.unwrap_or_else(|| {
+            fit_range(
+                plot.series
+                    .iter()
+                    .flat_map(|s| s.points.iter().map(|p| p.y)),
+            )
+        });
+        meshes.insert(parts.mesh.clone(), plot_mesh(&plot, x, y, area));
+
+        if let Ok(mut transform) = transforms.get_mut(parts.background) {
+            transform.translation = to_world(top_left + PANEL / 2.).extend(-1.);
+        }
+        if let Ok((mut label, mut style)) = labels.get_mut(parts.label) {
+            label.sections[0].value = format!(
+                "{}\n{}\n{} {} to {}, {} {} to {}",
+                plot.title,
+                plot.notes,
+                plot.x_label,
+                number(x[0]),
+                number(x[1]),
+                plot.y_label,
+                number(y[0]),
+                number(y[1])
+            );
+            *style = Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(top_left.x + PADDING),
+                top: Val::Px(top_left.y + PADDING),
+                ..default()
+            };
+        }
+    }
+}
+
+/// The smallest range holding every finite value, widened around a single value
+fn fit_range(values: impl Iterator<Item = f32>) -> [f32; 2] {
+    let (min, max) = values
+        .filter(|value| value.is_finite())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+        });
+
+    if min > max {
+        [0., 1.]
+    } else if min == max {
+        [min - 0.5, max + 0.5]
+    } else {
+        [min, max]
+    }
+}
+
+/// Writes a number compactly whatever its size
+fn number(value: f32) -> String {
+    if value == 0. || (1e-2..1e4).contains(&value.abs()) {
+        format!("{value:.3}")
+    } else {
+        format!("{value:.2e}")
+    }
+}
+
+/// The frame of the plot and its series as a list of line segments within `area`
+fn plot_mesh(plot: &Plot, x: [f32; 2], y: [f32; 2], area: Rect) -> Mesh {
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut colors: Vec<[f32; 4]> = Vec::new();
+    let mut segment = |from: Vec2, to: Vec2, color: Color| {
+        positions.extend([from.extend(0.), to.extend(0.)]);
+        colors.extend([color.as_linear_rgba_f32(); 2]);
+    };
+
+    let [a, b, c, d] = [
+        area.min,
+        Vec2::new(area.max.x, area.min.y),
+        area.max,
+        Vec2::new(area.min.x, area.max.y),
+    ];
+    for (from, to) in [(a, b), (b, c), (c, d), (d, a)] {
+        segment(from, to, Color::GRAY);
+    }
+
+    // Where a point is drawn, if it is within the ranges shown
+    let place = |p: Vec2| {
+        let fraction = Vec2::new((p.x - x[0]) / (x[1] - x[0]), (p.y - y[0]) / (y[1] - y[0]));
+        let inside = fraction.is_finite()
+            && fraction.cmpge(Vec2::ZERO).all()
+            && fraction.cmple(Vec2::ONE).all();
+        inside.then(|| area.min + fraction * area.size())
+    };
+
+    for series in &plot.series {
+        if series.scatter {
+            for point in series.points.iter().filter_map(|&p| place(p)) {
+                segment(
+                    point - Vec2::X * CROSS,
+                    point + Vec2::X * CROSS,
+                    series.color,
+                );
+                segment(
+                    point - Vec2::Y * CROSS,
+                    point + Vec2::Y * CROSS,
+                    series.color,
+                );
+            }
+            continue;
+        }
+        for pair in series.points.windows(2) {
+            if let (Some(from), Some(to)) = (place(pair[0]), place(pair[1])) {
+                segment(from, to, series.color);
+            }
+        }
+    }
+
+    Mesh::new(PrimitiveTopology::LineList)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+}
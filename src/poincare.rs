@@ -0,0 +1,221 @@
+//! Poincaré sections, the points where a trajectory passes through a plane
+//!
+//! A section turns the flow into a map from one crossing to the next, with one dimension
+//! fewer to look at. Crossings are found between two steps where the signed distance to the
+//! plane changes sign, and pinned down within the step on the cubic that matches the state
+//! and the derivative at both ends of it, so they are far more precise than the step size.
+
+use std::{fmt, str::FromStr};
+
+use glam::{DVec2, DVec3};
+
+use crate::{DynamicalSystem, Simulation};
+
+/// How many times a step is halved to pin down where something happens within it
+const BISECTIONS: usize = 50;
+
+/// The plane of the points `p` with `normal · p = offset`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    /// Always of unit length
+    pub normal: DVec3,
+    pub offset: f64,
+}
+
+impl Plane {
+    /// The plane facing along `normal`, at `offset` from the origin in units of its length
+    ///
+    /// # Panics
+    /// If `normal` is zero or not finite.
+    pub fn new(normal: DVec3, offset: f64) -> Self {
+        let length = normal.length();
+        assert!(
+            length > 0. && length.is_finite(),
+            "the normal of a plane needs a direction"
+        );
+        Self {
+            normal: normal / length,
+            offset: offset / length,
+        }
+    }
+
+    /// How far `p` is from the plane, positive on the side the normal points to
+    pub fn distance(&self, p: DVec3) -> f64 {
+        self.normal.dot(p) - self.offset
+    }
+
+    /// The point of the plane closest to `p`
+    pub fn project(&self, p: DVec3) -> DVec3 {
+        p - self.normal * self.distance(p)
+    }
+
+    /// Two unit vectors along the plane, at right angles to each other
+    ///
+    /// They are the two coordinate axes least aligned with the normal, in order, with what
+    /// lies along the normal taken out, so the plane `z = c` has the axes `x` and `y`.
+    pub fn axes(&self) -> [DVec3; 2] {
+        let mut axes = [DVec3::X, DVec3::Y, DVec3::Z];
+        let along = self.normal.abs();
+        let skipped = if along.x >= along.y && along.x >= along.z {
+            0
+        } else if along.y >= along.z {
+            1
+        } else {
+            2
+        };
+        axes[skipped..].rotate_left(1);
+
+        let u = (axes[0] - self.normal * self.normal.dot(axes[0])).normalize();
+        let v = axes[1] - self.normal * self.normal.dot(axes[1]);
+        [u, (v - u * u.dot(v)).normalize()]
+    }
+
+    /// The coordinates of `p` along [`Plane::axes`], for plotting the section flat
+    pub fn coordinates(&self, p: DVec3) -> DVec2 {
+        let [u, v] = self.axes();
+        DVec2::new(u.dot(p), v.dot(p))
+    }
+}
+
+/// Which way through the plane a crossing has to go to count
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    /// The way the normal points
+    Up,
+    /// Against the normal
+    #[default]
+    Down,
+    Both,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 3] = [Direction::Up, Direction::Down, Direction::Both];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Both => "both",
+        }
+    }
+
+    /// Whether going from `from` to `to` distance from the plane counts as a crossing
+    fn crosses(self, from: f64, to: f64) -> bool {
+        let up = from < 0. && to >= 0.;
+        let down = from > 0. && to <= 0.;
+        match self {
+            Direction::Up => up,
+            Direction::Down => down,
+            Direction::Both => up || down,
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Direction::ALL
+            .into_iter()
+            .find(|direction| direction.name() == s)
+            .ok_or_else(|| format!("unknown direction `{s}`, expected one of: up, down, both"))
+    }
+}
+
+/// A point where a trajectory passed through the plane
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Crossing {
+    pub time: f64,
+    pub point: DVec3,
+}
+
+/// A plane and the direction trajectories have to cross it in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Section {
+    pub plane: Plane,
+    pub direction: Direction,
+}
+
+impl Section {
+    /// Where the step of `system` from the `(time, state)` pair `from` to `to` crosses the
+    /// plane, if it does in the right direction
+    pub fn crossing(
+        &self,
+        system: &dyn DynamicalSystem,
+        from: (f64, DVec3),
+        to: (f64, DVec3),
+    ) -> Option<Crossing> {
+        let ((t0, p0), (t1, p1)) = (from, to);
+        let (d0, d1) = (self.plane.distance(p0), self.plane.distance(p1));
+        if !self.direction.crosses(d0, d1) {
+            return None;
+        }
+
+        let at = interpolant(system, from, to);
+        let s = sign_change(|s| self.plane.distance(at(s)), d0 < 0.);
+        Some(Crossing {
+            time: t0 + s * (t1 - t0),
+            point: at(s),
+        })
+    }
+
+    /// The crossings over the next `steps` steps of `simulation`
+    pub fn crossings<'a>(
+        &'a self,
+        simulation: &'a mut Simulation,
+        steps: usize,
+    ) -> impl Iterator<Item = Crossing> + 'a {
+        (0..steps).filter_map(move |_| {
+            let from = (simulation.time, simulation.state);
+            simulation.step();
+            let to = (simulation.time, simulation.state);
+            self.crossing(&*simulation.system, from, to)
+        })
+    }
+}
+
+/// The state during the step of `system` from the `(time, state)` pair `from` to `to`, as a
+/// function of how far through the step it is from 0 to 1
+///
+/// This is the cubic Hermite interpolant, which matches the state and the derivative at both
+/// ends of the step.
+pub(crate) fn interpolant(
+    system: &dyn DynamicalSystem,
+    from: (f64, DVec3),
+    to: (f64, DVec3),
+) -> impl Fn(f64) -> DVec3 {
+    let ((t0, p0), (t1, p1)) = (from, to);
+    let h = t1 - t0;
+    let (m0, m1) = (system.derivative(p0) * h, system.derivative(p1) * h);
+    move |s| {
+        let s2 = s * s;
+        let s3 = s2 * s;
+        p0 * (2. * s3 - 3. * s2 + 1.)
+            + m0 * (s3 - 2. * s2 + s)
+            + p1 * (-2. * s3 + 3. * s2)
+            + m1 * (s3 - s2)
+    }
+}
+
+/// Where between 0 and 1 `f` changes sign, given that it starts out negative when
+/// `negative` and positive otherwise, and ends up on the other side
+///
+/// Halving the interval keeps the change inside it, however `f` behaves in between.
+pub(crate) fn sign_change(f: impl Fn(f64) -> f64, negative: bool) -> f64 {
+    let (mut low, mut high) = (0., 1.);
+    for _ in 0..BISECTIONS {
+        let middle = (low + high) / 2.;
+        if (f(middle) < 0.) == negative {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    (low + high) / 2.
+}
@@ -0,0 +1,206 @@
+//! The power spectral density of the coordinates of a trajectory, how its variance is spread
+//! over frequencies
+//!
+//! A periodic orbit puts all its power into a few sharp peaks, a chaotic one spreads it over a
+//! broad band, often with a bump at the frequency it goes around its loops. The spectrum is
+//! estimated with Welch's method: the samples are cut into segments overlapping by half, each
+//! is tapered by a window and transformed with a fast Fourier transform, and the squared
+//! magnitudes are averaged over the segments.
+
+use std::{f64::consts::TAU, fmt, str::FromStr};
+
+use glam::{DVec2, DVec3};
+
+/// The taper each segment is multiplied by before it is transformed, which trades how sharp a
+/// peak is against how far it leaks into the frequencies around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Window {
+    /// No taper at all, the sharpest peaks and the most leakage
+    Rectangular,
+    #[default]
+    Hann,
+    Hamming,
+    /// The least leakage and the broadest peaks
+    Blackman,
+}
+
+impl Window {
+    pub const ALL: [Window; 4] = [
+        Window::Rectangular,
+        Window::Hann,
+        Window::Hamming,
+        Window::Blackman,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Window::Rectangular => "rectangular",
+            Window::Hann => "hann",
+            Window::Hamming => "hamming",
+            Window::Blackman => "blackman",
+        }
+    }
+
+    /// The weight of sample `i` of a segment of `n`
+    pub fn weight(self, i: usize, n: usize) -> f64 {
+        let x = TAU * i as f64 / n as f64;
+        match self {
+            Window::Rectangular => 1.,
+            Window::Hann => 0.5 - 0.5 * x.cos(),
+            Window::Hamming => 0.54 - 0.46 * x.cos(),
+            Window::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2. * x).cos(),
+        }
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Window {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Window::ALL
+            .into_iter()
+            .find(|window| window.name() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown window `{s}`, expected one of: rectangular, hann, hamming, blackman"
+                )
+            })
+    }
+}
+
+/// The discrete Fourier transform of `values` in place, each a complex number as `(re, im)`
+///
+/// # Panics
+/// If the number of values is not a power of two.
+pub fn fft(values: &mut [DVec2]) {
+    let n = values.len();
+    assert!(n.is_power_of_two(), "{n} values is not a power of two");
+    if n == 1 {
+        return;
+    }
+
+    // The butterflies below take their inputs in bit reversed order
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+
+    let mut length = 2;
+    while length <= n {
+        let half = length / 2;
+        let step = DVec2::from_angle(-TAU / length as f64);
+        for block in values.chunks_exact_mut(length) {
+            let mut twiddle = DVec2::X;
+            for k in 0..half {
+                let (even, odd) = (block[k], block[k + half].rotate(twiddle));
+                block[k] = even + odd;
+                block[k + half] = even - odd;
+                twiddle = twiddle.rotate(step);
+            }
+        }
+        length *= 2;
+    }
+}
+
+/// The power spectral density of `x`, `y` and `z`, from zero up to the Nyquist frequency
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerSpectrum {
+    /// The spacing of the frequencies, the `k`-th is `k * resolution`
+    pub resolution: f64,
+    /// The density of each coordinate at each frequency, in squared units per unit of
+    /// frequency, so that it adds up to the variance
+    pub density: Vec<DVec3>,
+    /// How many segments were averaged
+    pub segments: usize,
+}
+
+impl PowerSpectrum {
+    /// Estimates the spectrum of `samples` taken `interval` apart in time, over segments of
+    /// `segment` samples, or `None` when there are fewer samples than that
+    ///
+    /// # Panics
+    /// If `segment` is not a power of two.
+    pub fn new(samples: &[DVec3], interval: f64, window: Window, segment: usize) -> Option<Self> {
+        assert!(
+            segment.is_power_of_two(),
+            "a segment of {segment} is not a power of two"
+        );
+        if samples.len() < segment {
+            return None;
+        }
+
+        let weights: Vec<f64> = (0..segment).map(|i| window.weight(i, segment)).collect();
+        let energy: f64 = weights.iter().map(|w| w * w).sum();
+        let mut density = vec![DVec3::ZERO; segment / 2 + 1];
+        let mut segments = 0;
+
+        let mut columns = vec![vec![DVec2::ZERO; segment]; 3];
+        for start in (0..=samples.len() - segment).step_by((segment / 2).max(1)) {
+            let samples = &samples[start..start + segment];
+            let mean = samples.iter().sum::<DVec3>() / segment as f64;
+            for (axis, column) in columns.iter_mut().enumerate() {
+                for ((value, p), w) in column.iter_mut().zip(samples).zip(&weights) {
+                    *value = DVec2::new((p[axis] - mean[axis]) * w, 0.);
+                }
+                fft(column);
+            }
+            for (k, density) in density.iter_mut().enumerate() {
+                *density +=
+                    DVec3::from_array([0, 1, 2].map(|axis| columns[axis][k].length_squared()));
+            }
+            segments += 1;
+        }
+
+        // Both halves of the transform of a real signal carry the same power, so every
+        // frequency but zero and the Nyquist frequency counts twice
+        let scale = interval / (energy * segments as f64);
+        let last = density.len() - 1;
+        for (k, density) in density.iter_mut().enumerate() {
+            let sides = if k == 0 || k == last { 1. } else { 2. };
+            *density *= sides * scale;
+        }
+
+        Some(Self {
+            resolution: 1. / (interval * segment as f64),
+            density,
+            segments,
+        })
+    }
+
+    /// The frequency of each value of [`PowerSpectrum::density`]
+    pub fn frequencies(&self) -> impl Iterator<Item = f64> + '_ {
+        (0..self.density.len()).map(|k| k as f64 * self.resolution)
+    }
+
+    /// The frequency with the most power for each coordinate, leaving out zero
+    pub fn peaks(&self) -> DVec3 {
+        DVec3::from_array([0, 1, 2].map(|axis| {
+            let k = (1..self.density.len())
+                .max_by(|&a, &b| self.density[a][axis].total_cmp(&self.density[b][axis]))
+                .unwrap_or(0);
+            k as f64 * self.resolution
+        }))
+    }
+
+    /// The frequency below which `fraction` of the power of all three coordinates lies
+    pub fn bandwidth(&self, fraction: f64) -> f64 {
+        let total: f64 = self.density.iter().map(|d| d.x + d.y + d.z).sum();
+        let mut sum = 0.;
+        for (k, d) in self.density.iter().enumerate() {
+            sum += d.x + d.y + d.z;
+            if sum >= fraction * total {
+                return k as f64 * self.resolution;
+            }
+        }
+        (self.density.len() - 1) as f64 * self.resolution
+    }
+}
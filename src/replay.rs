@@ -0,0 +1,90 @@
+//! Drawing a recorded trajectory instead of simulating one
+
+use std::{fs::File, io::BufReader, path::Path};
+
+use bevy::{math::DVec3, prelude::*};
+use lorenz_attractor::{export::Recording, Attractor, DynamicalSystem};
+
+use crate::{scene::ColorMap, LineMaterial, StepsPerFrame, Trajectory};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_replay)
+            .add_systems(Update, replay_system);
+    }
+}
+
+/// The recording to draw, when there is one no trajectories are simulated
+///
+/// Its samples are moved out into the trajectory drawing them once that is spawned.
+#[derive(Resource)]
+pub struct Recorded(pub Recording);
+
+impl Recorded {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        File::open(path)
+            .and_then(|file| Recording::read(BufReader::new(file)))
+            .map(Recorded)
+            .map_err(|err| format!("could not read {}: {err}", path.display()))
+    }
+
+    /// The built-in system the recording was made with, set to the recorded parameters
+    pub fn system(&self) -> Option<Attractor> {
+        let header = self.0.header.as_ref()?;
+        let mut system: Attractor = header.system.parse().ok()?;
+
+        for (name, value) in &header.params {
+            let index = system.param_index(name)?;
+            system.params_mut()[index] = *value;
+        }
+        Some(system)
+    }
+}
+
+/// The recorded samples that have yet to be drawn
+#[derive(Component)]
+struct Replay {
+    samples: std::vec::IntoIter<DVec3>,
+}
+
+fn spawn_replay(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    recorded: Option<ResMut<Recorded>>,
+) {
+    let Some(mut recorded) = recorded else {
+        return;
+    };
+
+    // The resource stays for the whole run to tell that there is a recording, only its
+    // samples are handed over
+    let samples: Vec<_> = std::mem::take(&mut recorded.0.samples)
+        .into_iter()
+        .map(|(_, p)| p)
+        .collect();
+    commands.spawn((
+        Replay {
+            samples: samples.into_iter(),
+        },
+        Trajectory::new(materials.add(LineMaterial {
+            color: Color::WHITE,
+        })),
+        SpatialBundle::default(),
+    ));
+}
+
+/// Draws as many samples each frame as the simulation would have produced
+fn replay_system(
+    steps: Res<StepsPerFrame>,
+    color_map: Res<ColorMap>,
+    mut replays: Query<(&mut Replay, &mut Trajectory)>,
+) {
+    for (mut replay, mut trajectory) in &mut replays {
+        for p in replay.samples.by_ref().take(steps.0 as usize) {
+            let translation = p.as_vec3();
+            trajectory.push(translation, color_map.color(translation, None));
+        }
+    }
+}
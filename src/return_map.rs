@@ -0,0 +1,143 @@
+//! A plot of Lorenz's return map, every local maximum of `z` against the one before it
+//!
+//! Maxima are found as the trajectories are simulated, see [`ReturnMap::record`], and each
+//! trajectory's are scattered in its own colour over the diagonal `z_n+1 = z_n`. They are
+//! cleared whenever a parameter changes.
+
+use bevy::{math::DVec3, prelude::*, utils::HashMap};
+use lorenz_attractor::{maxima::maximum, DynamicalSystem};
+
+use crate::{
+    params::Params,
+    plot::{Plot, Series},
+    scene::Scene,
+};
+
+/// At most this many of the latest points of each trajectory are plotted
+const PLOTTED_POINTS: usize = 5000;
+
+pub struct ReturnMapPlugin;
+
+impl Plugin for ReturnMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_plot)
+            .add_systems(
+                Update,
+                clear_return_map
+                    .run_if(resource_exists::<ReturnMap>().and_then(resource_changed::<Params>()))
+                    .before(crate::simulate),
+            )
+            .add_systems(
+                Update,
+                update_plot
+                    .run_if(resource_exists_and_changed::<ReturnMap>())
+                    .after(crate::simulate),
+            );
+    }
+}
+
+/// The maxima of each trajectory so far
+#[derive(Resource, Default)]
+pub struct ReturnMap(HashMap<Entity, Maxima>);
+
+struct Maxima {
+    last: f64,
+    /// Each maximum after the first against the one before it
+    points: Vec<Vec2>,
+    color: Color,
+}
+
+impl ReturnMap {
+    /// Records the maximum of `z`, if any, in the step of the trajectory `entity` from the
+    /// `(time, state)` pair `from` to `to`, returning whether there was one
+    pub fn record(
+        &mut self,
+        entity: Entity,
+        system: &dyn DynamicalSystem,
+        from: (f64, DVec3),
+        to: (f64, DVec3),
+        color: Option<Color>,
+    ) -> bool {
+        let Some(maximum) = maximum(system, from, to) else {
+            return false;
+        };
+        let z = maximum.point.z;
+
+        self.0
+            .entry(entity)
+            .and_modify(|maxima| {
+                maxima.points.push(Vec2::new(maxima.last as f32, z as f32));
+                maxima.last = z;
+            })
+            .or_insert_with(|| Maxima {
+                last: z,
+                points: Vec::new(),
+                color: color.unwrap_or(Color::ORANGE),
+            });
+        true
+    }
+}
+
+#[derive(Component)]
+struct ReturnMapPlot;
+
+fn spawn_plot(mut commands: Commands, scene: Res<Scene>) {
+    if !scene.return_map.show {
+        return;
+    }
+
+    commands.init_resource::<ReturnMap>();
+    commands.spawn((
+        Plot {
+            title: "return map of the maxima of z".into(),
+            x_label: "z_n".into(),
+            y_label: "z_n+1".into(),
+            ..default()
+        },
+        ReturnMapPlot,
+    ));
+}
+
+fn clear_return_map(mut return_map: ResMut<ReturnMap>) {
+    return_map.0.clear();
+}
+
+fn update_plot(return_map: Res<ReturnMap>, mut plots: Query<&mut Plot, With<ReturnMapPlot>>) {
+    let Ok(mut plot) = plots.get_single_mut() else {
+        return;
+    };
+
+    let mut series: Vec<Series> = return_map
+        .0
+        .values()
+        .map(|maxima| Series {
+            points: maxima.points[maxima.points.len().saturating_sub(PLOTTED_POINTS)..].to_vec(),
+            color: maxima.color,
+            scatter: true,
+        })
+        .collect();
+
+    let (low, high) = series
+        .iter()
+        .flat_map(|series| &series.points)
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), p| {
+            (low.min(p.min_element()), high.max(p.max_element()))
+        });
+    let count: usize = return_map
+        .0
+        .values()
+        .map(|maxima| maxima.points.len())
+        .sum();
+    plot.notes = format!("{count} pairs of successive maxima");
+    if low < high {
+        series.insert(
+            0,
+            Series {
+                points: vec![Vec2::splat(low), Vec2::splat(high)],
+                color: Color::GRAY,
+                scatter: false,
+            },
+        );
+    }
+    plot.series = series;
+}
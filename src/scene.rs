@@ -0,0 +1,685 @@
+//! Scene files describing everything needed to start the visualisation
+//!
+//! Scenes are written in TOML, every table and field is optional and falls back to the
+//! defaults the app uses without a scene. Most of those defaults depend on the system, the
+//! values below are the ones for the Lorenz system:
+//!
+//! ```toml
+//! [system]
+//! name = "lorenz"
+//! # Every other key sets the parameter of that name
+//! sigma = 10.0
+//! rho = 28.0
+//! beta = 2.6666667
+//! # In place of `name`, a system of your own can be written out as one equation for each
+//! # variable, see `lorenz_attractor::equations`. Its parameters are set the same way
+//! # equations = ["dx = s * (y - x)", "dy = x * (r - z) - y", "dz = x * y - b * z"]
+//!
+//! [integration]
+//! integrator = "rk4"
+//! dt = 0.001
+//! steps_per_frame = 50
+//!
+//! [camera]
+//! position = [-100.0, 0.0, 150.0]
+//! look_at = [0.0, 0.0, 0.0]
+//!
+//! [color_map]
+//! x = [-13.0, 13.0]
+//! hue = [25.0, 35.0]
+//! y = [-28.0, 28.0]
+//! lightness = [0.3, 0.7]
+//! saturation = 0.8
+//! alpha = 0.5
+//! # The hues of the left and right wings, taking over from `x`: each loop is drawn in the hue
+//! # of the wing it went around at its top, the last local maximum of z. Off unless given
+//! wings = [210.0, 25.0]
+//!
+//! # Several trajectories started `epsilon` apart around the first one's initial state, each
+//! # in a colour of its own, drawn instead of the trajectories. Off unless a size is given
+//! [ensemble]
+//! size = 0
+//! epsilon = 1e-5
+//!
+//! # A copy of the first trajectory started `epsilon` along x from it, and a plot of how far
+//! # apart the two are over time. Hidden unless shown, cannot be combined with an ensemble
+//! [separation]
+//! show = false
+//! epsilon = 1e-8
+//!
+//! # The points where the trajectories cross the plane `normal · p = offset` going the given
+//! # way along the normal: up, down or both. The offset is an expression in the parameters,
+//! # `rho - 1` for a system with a `rho`, otherwise it goes through the middle of the view.
+//! # Hidden unless shown
+//! [poincare]
+//! show = false
+//! normal = [0.0, 0.0, 1.0]
+//! offset = "rho - 1"
+//! direction = "down"
+//!
+//! # A plot of every local maximum of z against the one before it, Lorenz's return map
+//! [return_map]
+//! show = false
+//!
+//! # The unstable periodic orbits closing within `max_crossings` crossings of the Poincaré
+//! # section, one of them drawn at a time. Each is named by the wings it goes around, like
+//! # "LLR", and `selected` picks the one drawn first. Not searched for unless shown
+//! [orbits]
+//! show = false
+//! max_crossings = 6
+//! selected = "LR"
+//!
+//! # Markers on the equilibria, labelled with what the flow does around each
+//! [equilibria]
+//! show = false
+//!
+//! # A live plot of the power spectral density of x, y and z along the first trajectory,
+//! # sampled every `every` steps and averaged over segments of `segment` samples, a power of
+//! # two, each tapered by the window: rectangular, hann, hamming or blackman
+//! [power_spectrum]
+//! show = false
+//! window = "hann"
+//! segment = 4096
+//! every = 10
+//!
+//! [[trajectory]]
+//! initial = [0.1, 0.0, 0.1]
+//! color = "#3080ff"
+//! ```
+
+use std::{collections::BTreeMap, fmt, fs, io, path::Path, str::FromStr};
+
+use bevy::{math::DVec3, prelude::*};
+use lorenz_attractor::{
+    expr::Expr,
+    orbits,
+    poincare::{Direction, Plane, Section},
+    power_spectrum::Window,
+    symbols::Wing,
+    system::Framing,
+    Attractor, DynamicalSystem, Equations, Integrator, Simulation,
+};
+use serde::{de, Deserialize, Deserializer};
+
+use crate::map_range;
+
+#[derive(Resource, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scene {
+    pub system: SystemConfig,
+    pub integration: IntegrationConfig,
+    pub camera: CameraConfig,
+    pub color_map: ColorMapConfig,
+    pub ensemble: EnsembleConfig,
+    pub separation: SeparationConfig,
+    pub poincare: PoincareConfig,
+    pub return_map: ReturnMapConfig,
+    pub orbits: OrbitsConfig,
+    pub equilibria: EquilibriaConfig,
+    pub power_spectrum: PowerSpectrumConfig,
+    #[serde(rename = "trajectory")]
+    pub trajectories: Vec<TrajectoryConfig>,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self {
+            system: default(),
+            integration: default(),
+            camera: default(),
+            color_map: default(),
+            ensemble: default(),
+            separation: default(),
+            poincare: default(),
+            return_map: default(),
+            orbits: default(),
+            equilibria: default(),
+            power_spectrum: default(),
+            trajectories: vec![TrajectoryConfig::default()],
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SystemConfig {
+    /// One of the built-in systems, the Lorenz system unless there are `equations`
+    #[serde(deserialize_with = "optional_from_str")]
+    pub name: Option<Attractor>,
+    /// One equation for the derivative of each variable, like `dx = s * (y - x)`
+    pub equations: Option<Vec<String>>,
+    /// Values replacing the system's default parameters, by name
+    #[serde(flatten)]
+    pub params: BTreeMap<String, f64>,
+}
+
+impl SystemConfig {
+    /// The system described, with its parameters set
+    pub fn build(&self) -> Result<Box<dyn DynamicalSystem>, String> {
+        match (&self.name, &self.equations) {
+            (Some(_), Some(_)) => Err("give either a name or equations, not both".into()),
+            (_, Some(equations)) => {
+                let params: Vec<_> = self
+                    .params
+                    .iter()
+                    .map(|(name, &value)| (name.clone(), value))
+                    .collect();
+                let equations =
+                    Equations::new(equations, &params).map_err(|err| err.to_string())?;
+                Ok(Box::new(equations))
+            }
+            (name, None) => {
+                let mut system = name.clone().unwrap_or_default();
+                for (name, &value) in &self.params {
+                    let index = system
+                        .param_index(name)
+                        .ok_or_else(|| unknown_param(&system, name))?;
+                    system.params_mut()[index] = value;
+                }
+                Ok(Box::new(system))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IntegrationConfig {
+    #[serde(deserialize_with = "from_str")]
+    pub integrator: Integrator,
+    /// Falls back to the time step the system suggests
+    pub dt: Option<f64>,
+    pub steps_per_frame: u32,
+}
+
+impl Default for IntegrationConfig {
+    fn default() -> Self {
+        Self {
+            integrator: Integrator::default(),
+            dt: None,
+            steps_per_frame: 50,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnsembleConfig {
+    pub size: usize,
+    pub epsilon: f64,
+}
+
+impl Default for EnsembleConfig {
+    fn default() -> Self {
+        Self {
+            size: 0,
+            epsilon: 1e-5,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SeparationConfig {
+    pub show: bool,
+    pub epsilon: f64,
+}
+
+impl Default for SeparationConfig {
+    fn default() -> Self {
+        Self {
+            show: false,
+            epsilon: 1e-8,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PoincareConfig {
+    pub show: bool,
+    pub normal: [f64; 3],
+    /// An expression in the parameters of the system, falls back to `rho - 1` when it has a
+    /// `rho` and to the plane through the middle of the system's framing otherwise
+    pub offset: Option<String>,
+    #[serde(deserialize_with = "from_str")]
+    pub direction: Direction,
+}
+
+impl Default for PoincareConfig {
+    fn default() -> Self {
+        Self {
+            show: false,
+            normal: [0., 0., 1.],
+            offset: None,
+            direction: Direction::default(),
+        }
+    }
+}
+
+impl PoincareConfig {
+    /// The section with the parameters `system` has at the moment
+    pub fn section(&self, system: &dyn DynamicalSystem) -> Result<Section, String> {
+        let normal = DVec3::from(self.normal);
+        let offset = match &self.offset {
+            Some(offset) => {
+                let expr = Expr::parse(offset, &system.param_names())
+                    .map_err(|err| format!("offset: {err}"))?;
+                expr.evaluate(DVec3::ZERO, system.params())
+            }
+            None => match system.param_index("rho") {
+                Some(rho) => system.params()[rho] - 1.,
+                None => normal.dot(system.framing().look_at),
+            },
+        };
+        if !offset.is_finite() {
+            return Err(format!("offset is {offset}, it must be a finite number"));
+        }
+
+        Ok(Section {
+            plane: Plane::new(normal, offset),
+            direction: self.direction,
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReturnMapConfig {
+    pub show: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OrbitsConfig {
+    pub show: bool,
+    pub max_crossings: usize,
+    pub selected: Option<String>,
+}
+
+impl Default for OrbitsConfig {
+    fn default() -> Self {
+        Self {
+            show: false,
+            max_crossings: 6,
+            selected: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EquilibriaConfig {
+    pub show: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PowerSpectrumConfig {
+    pub show: bool,
+    #[serde(deserialize_with = "from_str")]
+    pub window: Window,
+    /// How many samples each segment the spectrum is averaged over holds, a power of two
+    pub segment: usize,
+    /// How many steps apart the samples are taken
+    pub every: usize,
+}
+
+impl Default for PowerSpectrumConfig {
+    fn default() -> Self {
+        Self {
+            show: false,
+            window: Window::default(),
+            segment: 4096,
+            every: 10,
+        }
+    }
+}
+
+/// Where the camera starts, each field falls back to the system's framing
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CameraConfig {
+    pub position: Option<[f32; 3]>,
+    pub look_at: Option<[f32; 3]>,
+}
+
+impl CameraConfig {
+    fn position(&self, framing: &Framing) -> Vec3 {
+        self.position.map_or(framing.position.as_vec3(), Vec3::from)
+    }
+
+    fn look_at(&self, framing: &Framing) -> Vec3 {
+        self.look_at.map_or(framing.look_at.as_vec3(), Vec3::from)
+    }
+
+    pub fn transform(&self, framing: &Framing) -> Transform {
+        Transform::from_translation(self.position(framing))
+            .looking_at(self.look_at(framing), Vec3::Y)
+    }
+}
+
+/// The hues of the left and right wings when colouring by wing is asked for without picking
+/// them, blue and orange
+pub const WING_HUES: [f32; 2] = [210., 25.];
+
+/// The colour map as written in a scene, the ranges of `x` and `y` fall back to those of the
+/// system's framing
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorMapConfig {
+    pub x: Option<[f32; 2]>,
+    pub hue: [f32; 2],
+    pub y: Option<[f32; 2]>,
+    pub lightness: [f32; 2],
+    pub saturation: f32,
+    pub alpha: f32,
+    pub wings: Option<[f32; 2]>,
+}
+
+impl Default for ColorMapConfig {
+    fn default() -> Self {
+        Self {
+            x: None,
+            hue: [25., 35.],
+            y: None,
+            lightness: [0.3, 0.7],
+            saturation: 0.8,
+            alpha: 0.5,
+            wings: None,
+        }
+    }
+}
+
+impl ColorMapConfig {
+    pub fn color_map(&self, framing: &Framing) -> ColorMap {
+        let range = |[from, to]: [f64; 2]| [from as f32, to as f32];
+        ColorMap {
+            x: self.x.unwrap_or(range(framing.x)),
+            hue: self.hue,
+            y: self.y.unwrap_or(range(framing.y)),
+            lightness: self.lightness,
+            saturation: self.saturation,
+            alpha: self.alpha,
+            wings: self.wings,
+        }
+    }
+}
+
+/// How the colour of a trajectory follows its position
+///
+/// The hue is taken from `x` and the lightness from `y`, each is mapped linearly from the
+/// given range of positions onto the given range of values.
+#[derive(Resource, Debug, Clone)]
+pub struct ColorMap {
+    pub x: [f32; 2],
+    pub hue: [f32; 2],
+    pub y: [f32; 2],
+    pub lightness: [f32; 2],
+    pub saturation: f32,
+    pub alpha: f32,
+    /// The hues of the left and right wings, which replace the hue from `x` when given
+    pub wings: Option<[f32; 2]>,
+}
+
+impl ColorMap {
+    /// The colour at a point, `base` replaces the mapped hue and saturation when given
+    pub fn color(&self, translation: Vec3, base: Option<Color>) -> Color {
+        let l = map_range(
+            (self.y[0], self.y[1]),
+            (self.lightness[0], self.lightness[1]),
+            translation.y,
+        );
+
+        match base.map(|color| color.as_hsla()) {
+            Some(Color::Hsla {
+                hue, saturation, ..
+            }) => Color::hsla(hue, saturation, l, self.alpha),
+            _ => {
+                let h = map_range(
+                    (self.x[0], self.x[1]),
+                    (self.hue[0], self.hue[1]),
+                    translation.x,
+                );
+                Color::hsla(h, self.saturation, l, self.alpha)
+            }
+        }
+    }
+
+    /// The colour of a loop around `wing`, to be passed as the base of [`ColorMap::color`],
+    /// when the map colours by wing
+    pub fn wing_color(&self, wing: Wing) -> Option<Color> {
+        let [left, right] = self.wings?;
+        let hue = match wing {
+            Wing::Left => left,
+            Wing::Right => right,
+        };
+        Some(Color::hsl(hue, self.saturation, 0.5))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrajectoryConfig {
+    /// Falls back to the initial state the system suggests
+    #[serde(default)]
+    pub initial: Option<[f64; 3]>,
+    /// Overrides the hue and saturation of the colour map, as a hex colour
+    #[serde(default, deserialize_with = "hex_color")]
+    pub color: Option<Color>,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Read(io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Read(err) => write!(f, "could not read the scene: {err}"),
+            SceneError::Parse(err) => write!(f, "could not parse the scene: {err}"),
+            SceneError::Invalid(message) => write!(f, "invalid scene: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl Scene {
+    pub fn load(path: &Path) -> Result<Self, SceneError> {
+        let source = fs::read_to_string(path).map_err(SceneError::Read)?;
+        let scene: Scene = toml::from_str(&source).map_err(SceneError::Parse)?;
+        scene.validate().map_err(SceneError::Invalid)?;
+        Ok(scene)
+    }
+
+    /// Checks the values that parse fine but make no sense
+    fn validate(&self) -> Result<(), String> {
+        for (name, &value) in &self.system.params {
+            finite(&format!("system.{name}"), value)?;
+        }
+        let system = self
+            .system
+            .build()
+            .map_err(|err| format!("system: {err}"))?;
+
+        if let Some(dt) = self.integration.dt {
+            positive("integration.dt", dt)?;
+        }
+        if self.integration.steps_per_frame == 0 {
+            return Err("integration.steps_per_frame must be at least 1".into());
+        }
+
+        positive("ensemble.epsilon", self.ensemble.epsilon)?;
+        positive("separation.epsilon", self.separation.epsilon)?;
+        if self.separation.show && self.ensemble.size > 0 {
+            return Err("the separation cannot be shown along with an ensemble".into());
+        }
+
+        let normal = self.poincare.normal;
+        if !(normal.iter().all(|n| n.is_finite()) && normal.iter().any(|&n| n != 0.)) {
+            return Err("poincare.normal must be finite and not zero".into());
+        }
+        self.poincare
+            .section(&*system)
+            .map_err(|err| format!("poincare.{err}"))?;
+
+        if self.orbits.max_crossings == 0 {
+            return Err("orbits.max_crossings must be at least 1".into());
+        }
+        if let Some(selected) = &self.orbits.selected {
+            check_sequence(selected).map_err(|err| format!("orbits.selected: {err}"))?;
+        }
+
+        if !(self.power_spectrum.segment >= 2 && self.power_spectrum.segment.is_power_of_two()) {
+            return Err("power_spectrum.segment must be a power of two, at least 2".into());
+        }
+        if self.power_spectrum.every == 0 {
+            return Err("power_spectrum.every must be at least 1".into());
+        }
+
+        let camera = &self.camera;
+        for (name, value) in [
+            ("camera.position", camera.position),
+            ("camera.look_at", camera.look_at),
+        ] {
+            for coordinate in value.into_iter().flatten() {
+                finite(name, coordinate.into())?;
+            }
+        }
+        let framing = system.framing();
+        if camera.position(&framing) == camera.look_at(&framing) {
+            return Err("camera.look_at must be different from camera.position".into());
+        }
+
+        let color_map = &self.color_map;
+        for (name, range) in [("color_map.x", color_map.x), ("color_map.y", color_map.y)] {
+            let Some([from, to]) = range else {
+                continue;
+            };
+            if !(from.is_finite() && to.is_finite() && from != to) {
+                return Err(format!("{name} must be two different finite numbers"));
+            }
+        }
+        for (name, value) in [
+            ("color_map.lightness", color_map.lightness),
+            ("color_map.saturation", [color_map.saturation; 2]),
+            ("color_map.alpha", [color_map.alpha; 2]),
+        ] {
+            if !value.iter().all(|v| (0. ..=1.).contains(v)) {
+                return Err(format!("{name} must be between 0 and 1"));
+            }
+        }
+        for hue in color_map.hue {
+            finite("color_map.hue", hue.into())?;
+        }
+        for hue in color_map.wings.into_iter().flatten() {
+            finite("color_map.wings", hue.into())?;
+        }
+
+        if self.trajectories.is_empty() {
+            return Err("at least one [[trajectory]] is needed".into());
+        }
+        for (index, trajectory) in self.trajectories.iter().enumerate() {
+            for coordinate in trajectory.initial.into_iter().flatten() {
+                finite(&format!("trajectory[{index}].initial"), coordinate)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The system with the scene's parameters
+    pub fn system(&self) -> Box<dyn DynamicalSystem> {
+        self.system
+            .build()
+            .expect("the system is checked before the scene is used")
+    }
+
+    /// The simulation of one of the trajectories, at its initial state
+    pub fn simulation(&self, trajectory: &TrajectoryConfig) -> Simulation {
+        let system = self.system();
+        let initial = trajectory
+            .initial
+            .map_or(system.initial_state(), DVec3::from);
+
+        let mut simulation = Simulation::new(system, initial);
+        simulation.dt = self.integration.dt.unwrap_or(simulation.dt);
+        simulation.integrator = self.integration.integrator;
+        simulation
+    }
+}
+
+/// Explains that `system` has no parameter called `name`
+fn unknown_param(system: &dyn DynamicalSystem, name: &str) -> String {
+    format!(
+        "{} has no parameter `{name}`, expected one of: {}",
+        system.name(),
+        system.param_names().join(", ")
+    )
+}
+
+/// Checks that `sequence` names an orbit, made of `L` and `R` and not a shorter sequence
+/// repeated
+pub fn check_sequence(sequence: &str) -> Result<(), String> {
+    if !sequence.chars().all(|wing| wing == 'L' || wing == 'R') {
+        return Err(format!("`{sequence}` has to be made of L and R"));
+    }
+    match orbits::canonical(sequence) {
+        Some(_) => Ok(()),
+        None if sequence.is_empty() => Err("an orbit has to go around a wing at least once".into()),
+        None => Err(format!(
+            "`{sequence}` goes around a shorter orbit more than once"
+        )),
+    }
+}
+
+fn finite(name: &str, value: f64) -> Result<(), String> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(format!("{name} must be a finite number"))
+    }
+}
+
+fn positive(name: &str, value: f64) -> Result<(), String> {
+    finite(name, value)?;
+    if value > 0. {
+        Ok(())
+    } else {
+        Err(format!("{name} must be greater than zero"))
+    }
+}
+
+fn from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(de::Error::custom)
+}
+
+fn optional_from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    from_str(deserializer).map(Some)
+}
+
+fn hex_color<'de, D>(deserializer: D) -> Result<Option<Color>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let hex = String::deserialize(deserializer)?;
+    Color::hex(&hex)
+        .map(Some)
+        .map_err(|_| de::Error::custom(format!("`{hex}` is not a hex colour like \"#3080ff\"")))
+}
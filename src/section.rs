@@ -0,0 +1,197 @@
+//! The Poincaré section: a see-through plane with a small cross wherever a trajectory went
+//! through it the right way
+//!
+//! Crossings are recorded as the trajectories are simulated, see [`Poincare::record`], and only
+//! the latest [`MAX_CROSSINGS`] are kept. The plane follows the parameters, it moves and the
+//! crossings are cleared whenever one changes.
+
+use std::collections::VecDeque;
+
+use bevy::{
+    math::DVec3,
+    prelude::*,
+    render::{mesh::PrimitiveTopology, view::NoFrustumCulling},
+};
+use lorenz_attractor::{poincare::Section, DynamicalSystem};
+
+use crate::{params::Params, scene::Scene, LineMaterial};
+
+/// At most this many of the latest crossings are drawn, the oldest make way for new ones
+const MAX_CROSSINGS: usize = 20_000;
+
+pub struct SectionPlugin;
+
+impl Plugin for SectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_section)
+            .add_systems(
+                Update,
+                move_section
+                    .run_if(resource_exists::<Poincare>().and_then(resource_changed::<Params>()))
+                    .before(crate::simulate),
+            )
+            .add_systems(
+                Update,
+                draw_crossings
+                    .run_if(resource_exists_and_changed::<Poincare>())
+                    .after(crate::simulate),
+            );
+    }
+}
+
+/// The section and the latest crossings of every trajectory
+#[derive(Resource)]
+pub struct Poincare {
+    section: Section,
+    /// Where each crossing is drawn, in the colour of its trajectory, oldest first
+    crossings: VecDeque<(Vec3, Color)>,
+}
+
+impl Poincare {
+    /// Records the crossing, if any, of the step of `system` from the `(time, state)` pair
+    /// `from` to `to`, returning whether there was one
+    pub fn record(
+        &mut self,
+        system: &dyn DynamicalSystem,
+        from: (f64, DVec3),
+        to: (f64, DVec3),
+        color: Color,
+    ) -> bool {
+        let crossing = self.section.crossing(system, from, to);
+        if let Some(crossing) = crossing {
+            if self.crossings.len() == MAX_CROSSINGS {
+                self.crossings.pop_front();
+            }
+            self.crossings.push_back((crossing.point.as_vec3(), color));
+        }
+        crossing.is_some()
+    }
+}
+
+/// The plane as drawn
+#[derive(Component)]
+struct SectionPlane;
+
+/// The crosses marking the crossings
+#[derive(Component)]
+struct Crossings(Handle<Mesh>);
+
+/// The size of the square the plane is drawn as, large enough to show the whole attractor cut
+fn plane_size(system: &dyn DynamicalSystem) -> f32 {
+    let framing = system.framing();
+    // The ranges of the framing cover the middle of the attractor
+    let width = |[from, to]: [f64; 2]| (to - from) as f32;
+    1.7 * width(framing.x).max(width(framing.y))
+}
+
+/// Where the plane is drawn, centered on the point of it closest to what the camera looks at
+fn plane_transform(section: &Section, system: &dyn DynamicalSystem) -> Transform {
+    let plane = section.plane;
+    Transform::from_translation(plane.project(system.framing().look_at).as_vec3())
+        .with_rotation(Quat::from_rotation_arc(Vec3::Z, plane.normal.as_vec3()))
+}
+
+fn spawn_section(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut planes: ResMut<Assets<StandardMaterial>>,
+    mut lines: ResMut<Assets<LineMaterial>>,
+    scene: Res<Scene>,
+    params: Res<Params>,
+) {
+    if !scene.poincare.show {
+        return;
+    }
+    let system = &*params.0;
+    let section = scene
+        .poincare
+        .section(system)
+        .expect("the section is checked before the scene is used");
+
+    let size = plane_size(system);
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(shape::Quad::new(Vec2::splat(size)).into()),
+            material: planes.add(StandardMaterial {
+                base_color: Color::rgba(0.4, 0.6, 1., 0.12),
+                unlit: true,
+                alpha_mode: AlphaMode::Blend,
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            }),
+            transform: plane_transform(&section, system),
+            ..default()
+        },
+        SectionPlane,
+    ));
+
+    let mesh = meshes.add(Mesh::new(PrimitiveTopology::LineList));
+    commands.spawn((
+        MaterialMeshBundle {
+            mesh: mesh.clone(),
+            material: lines.add(LineMaterial {
+                color: Color::WHITE,
+            }),
+            ..default()
+        },
+        // The bounds change with every crossing
+        NoFrustumCulling,
+        Crossings(mesh),
+    ));
+
+    commands.insert_resource(Poincare {
+        section,
+        crossings: VecDeque::new(),
+    });
+}
+
+fn move_section(
+    scene: Res<Scene>,
+    params: Res<Params>,
+    mut poincare: ResMut<Poincare>,
+    mut planes: Query<&mut Transform, With<SectionPlane>>,
+) {
+    let system = &*params.0;
+    match scene.poincare.section(system) {
+        Ok(section) => poincare.section = section,
+        // An offset that stopped making sense leaves the plane where it was
+        Err(err) => warn!("Poincaré section: {err}"),
+    }
+    poincare.crossings.clear();
+
+    for mut transform in &mut planes {
+        *transform = plane_transform(&poincare.section, system);
+    }
+}
+
+fn draw_crossings(
+    poincare: Res<Poincare>,
+    params: Res<Params>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    crossings: Query<&Crossings>,
+) {
+    let Ok(Crossings(mesh)) = crossings.get_single() else {
+        return;
+    };
+
+    let half = plane_size(&*params.0) / 300.;
+    let [u, v] = poincare
+        .section
+        .plane
+        .axes()
+        .map(|axis| axis.as_vec3() * half);
+    let mut positions = Vec::with_capacity(poincare.crossings.len() * 4);
+    let mut colors = Vec::with_capacity(poincare.crossings.len() * 4);
+    for &(point, color) in &poincare.crossings {
+        positions.extend([point - u, point + u, point - v, point + v]);
+        colors.extend([color.with_a(1.).as_linear_rgba_f32(); 4]);
+    }
+
+    meshes.insert(
+        mesh.clone(),
+        Mesh::new(PrimitiveTopology::LineList)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+            .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors),
+    );
+}
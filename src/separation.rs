@@ -0,0 +1,182 @@
+//! A copy of the first trajectory started a tiny distance away, and a plot of how far apart
+//! the two are
+//!
+//! The distance is plotted on a log scale against time, where its exponential growth shows
+//! up as a straight line until it saturates. The rate of that growth is fitted as it comes in.
+
+use bevy::{math::DVec3, prelude::*};
+use lorenz_attractor::divergence::{fit_growth, ExponentialFit};
+
+use crate::{
+    plot::{Plot, Series},
+    scene::{ColorMap, Scene},
+    spawn_simulated, LineMaterial, Simulated,
+};
+
+/// At most this many points of the separation are kept and plotted, however long it has run
+/// for: every other one is dropped whenever there would be more, and the frames recorded from
+/// then on are twice as far apart
+const RECORDED_POINTS: usize = 2000;
+
+pub struct SeparationPlugin;
+
+impl Plugin for SeparationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SeparationSeries>()
+            .add_systems(Startup, spawn_plot)
+            .add_systems(
+                Update,
+                (
+                    record_separation,
+                    update_plot.run_if(resource_changed::<SeparationSeries>()),
+                )
+                    .chain()
+                    .after(crate::simulate),
+            );
+    }
+}
+
+/// A trajectory started `epsilon` along `x` from another one
+#[derive(Component)]
+pub struct Perturbed {
+    reference: Entity,
+}
+
+/// The time and separation after every few frames since the trajectories last started, and
+/// the growth fitted to them
+#[derive(Resource)]
+struct SeparationSeries {
+    samples: Vec<(f64, f64)>,
+    /// How many frames apart the samples are taken
+    stride: usize,
+    /// How many frames have gone by since the last sample
+    frames: usize,
+    fit: Option<ExponentialFit>,
+}
+
+impl Default for SeparationSeries {
+    fn default() -> Self {
+        Self {
+            samples: Vec::new(),
+            stride: 1,
+            frames: 0,
+            fit: None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct SeparationPlot;
+
+/// Spawns the perturbed copy of the first of the scene's trajectories, drawn in grey
+pub fn spawn_perturbed(
+    commands: &mut Commands,
+    scene: &Scene,
+    reference: Entity,
+    material: Handle<LineMaterial>,
+    color_map: &ColorMap,
+) {
+    let mut simulation = scene.simulation(&scene.trajectories[0]);
+    let initial = simulation.state + DVec3::X * scene.separation.epsilon;
+    simulation.restart(initial);
+
+    let simulated = Simulated {
+        simulation,
+        initial,
+        color: Some(Color::WHITE),
+        wing: None,
+    };
+    let entity = spawn_simulated(commands, simulated, material, color_map);
+    commands.entity(entity).insert(Perturbed { reference });
+}
+
+fn spawn_plot(mut commands: Commands, scene: Res<Scene>) {
+    if !scene.separation.show {
+        return;
+    }
+
+    commands.spawn((
+        Plot {
+            title: format!("separation, started {:e} apart", scene.separation.epsilon),
+            x_label: "t".into(),
+            y_label: "log10 d".into(),
+            ..default()
+        },
+        SeparationPlot,
+    ));
+}
+
+fn record_separation(
+    mut series: ResMut<SeparationSeries>,
+    perturbed: Query<(&Perturbed, &Simulated)>,
+    simulated: Query<&Simulated>,
+) {
+    let Ok((perturbed, copy)) = perturbed.get_single() else {
+        return;
+    };
+    let Ok(reference) = simulated.get(perturbed.reference) else {
+        return;
+    };
+
+    // Both trajectories start again together whenever a parameter changes
+    if series.samples.last().is_some_and(|&(t, _)| copy.time < t) {
+        *series = default();
+    }
+    // Only a new sample changes what is plotted
+    let counter = series.bypass_change_detection();
+    counter.frames += 1;
+    if counter.frames < counter.stride {
+        return;
+    }
+    counter.frames = 0;
+
+    let series = &mut *series;
+    series
+        .samples
+        .push((copy.time, copy.state.distance(reference.state)));
+    if series.samples.len() > RECORDED_POINTS {
+        let mut index = 0;
+        series.samples.retain(|_| {
+            index += 1;
+            index % 2 == 1
+        });
+        series.stride *= 2;
+    }
+    series.fit = fit_growth(&series.samples);
+}
+
+fn update_plot(series: Res<SeparationSeries>, mut plots: Query<&mut Plot, With<SeparationPlot>>) {
+    let Ok(mut plot) = plots.get_single_mut() else {
+        return;
+    };
+
+    let points = series
+        .samples
+        .iter()
+        .map(|&(t, d)| Vec2::new(t as f32, d.log10() as f32))
+        .collect();
+    plot.series = vec![Series {
+        points,
+        color: Color::ORANGE,
+        scatter: false,
+    }];
+
+    let start = series.samples.first().map_or(0., |&(t, _)| t);
+    match series.fit {
+        Some(fit) => {
+            plot.notes = format!(
+                "growth rate {:.3} per unit of time, fitted up to t = {:.1}",
+                fit.rate, fit.until
+            );
+            let points = [start, fit.until]
+                .map(|t| Vec2::new(t as f32, fit.evaluate(t).log10() as f32))
+                .to_vec();
+            plot.series.push(Series {
+                points,
+                color: Color::CYAN,
+                scatter: false,
+            });
+        }
+        None => plot.notes = "growth rate: waiting for the separation to grow".into(),
+    }
+}
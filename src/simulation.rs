@@ -0,0 +1,112 @@
+//! Following a single point through the flow of a [`DynamicalSystem`]
+
+use glam::DVec3;
+
+use crate::{
+    adaptive::{DormandPrince, Tolerance},
+    DynamicalSystem, Integrator,
+};
+
+/// A single point moving through the flow of a system
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub system: Box<dyn DynamicalSystem>,
+    pub state: DVec3,
+    pub time: f64,
+    /// The size of each time step, or the interval between samples for adaptive integrators
+    pub dt: f64,
+    pub integrator: Integrator,
+    /// The step size controller used when `integrator` is adaptive
+    pub adaptive: DormandPrince,
+    /// Why the integration stopped, the state stays where it was from then on
+    pub failure: Option<String>,
+}
+
+impl Simulation {
+    /// Starts from `state` with the time step the system suggests
+    pub fn new(system: Box<dyn DynamicalSystem>, state: DVec3) -> Self {
+        Self {
+            dt: system.dt(),
+            system,
+            state,
+            time: 0.,
+            integrator: Integrator::default(),
+            adaptive: DormandPrince::new(Tolerance::default()),
+            failure: None,
+        }
+    }
+
+    /// Changes the parameters from the current state onwards
+    ///
+    /// # Panics
+    /// If `params` does not hold a value for every parameter of the system.
+    pub fn set_params(&mut self, params: &[f64]) {
+        self.system.params_mut().copy_from_slice(params);
+        self.failure = None;
+        // Anything the adaptive solver integrated ahead of the current time used the old ones
+        self.adaptive.reset(self.time, self.state);
+    }
+
+    /// Starts again from `state` at time zero
+    pub fn restart(&mut self, state: DVec3) {
+        self.state = state;
+        self.time = 0.;
+        self.failure = None;
+        self.adaptive.reset(self.time, self.state);
+    }
+
+    /// Fails with the reason the integration stopped, if it has
+    pub fn check(&self) -> Result<(), String> {
+        match &self.failure {
+            Some(failure) => Err(failure.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// The current time and state, followed by those after each of the next `steps` steps,
+    /// up to where the integration fails if it does
+    pub fn samples(&mut self, steps: usize) -> impl Iterator<Item = (f64, DVec3)> + '_ {
+        std::iter::once((self.time, self.state)).chain((0..steps).map_while(move |_| {
+            let state = self.step();
+            self.failure.is_none().then_some((self.time, state))
+        }))
+    }
+
+    /// Advances the state by `dt` and returns the new state
+    ///
+    /// Adaptive integrators take as many internal steps as they need and the state is
+    /// interpolated from their dense output.
+    ///
+    /// When the state stops being finite, or an adaptive integrator cannot meet its
+    /// tolerance, the integration stops: the reason is kept in `failure` and the state and
+    /// time stay where they were, see [`Simulation::check`].
+    pub fn step(&mut self) -> DVec3 {
+        if self.failure.is_some() {
+            return self.state;
+        }
+        let system = &*self.system;
+        let f = |p| system.derivative(p);
+
+        let state = if self.integrator.is_adaptive() {
+            self.adaptive.resume(self.time, self.state);
+            self.adaptive.sample(f, self.time + self.dt)
+        } else {
+            Ok(self.integrator.step(f, self.state, self.dt))
+        };
+        match state {
+            Ok(state) if state.is_finite() => {
+                self.state = state;
+                self.time += self.dt;
+            }
+            Ok(_) => {
+                self.failure = Some(format!(
+                    "the solution stopped being finite at t = {}",
+                    self.time
+                ))
+            }
+            Err(failure) => self.failure = Some(failure),
+        }
+
+        self.state
+    }
+}
@@ -0,0 +1,174 @@
+//! A live plot of the power spectral density of x, y and z
+//!
+//! The spectrum is estimated along a trajectory of its own, the scene's first one followed
+//! faster than the trajectories are drawn so that it fills in quickly. It is worked out again
+//! from the latest samples every time half a segment of new ones has come in, and starts over
+//! whenever a parameter changes.
+
+use std::collections::VecDeque;
+
+use bevy::{math::DVec3, prelude::*};
+use lorenz_attractor::{power_spectrum::PowerSpectrum, Simulation};
+
+use crate::{
+    params::Params,
+    plot::{Plot, Series},
+    scene::Scene,
+};
+
+/// How many steps the trajectory the spectrum is estimated along takes every frame
+const STEPS_PER_FRAME: usize = 2000;
+
+/// How many steps it takes before it is sampled, to settle onto the attractor
+const TRANSIENT: usize = 10_000;
+
+/// How many segments' worth of the latest samples the spectrum is averaged over
+const SEGMENTS: usize = 8;
+
+/// The share of the power the frequencies plotted hold, the rest is in a long flat tail
+const BANDWIDTH: f64 = 0.99;
+
+pub struct SpectrumPlugin;
+
+impl Plugin for SpectrumPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_plot).add_systems(
+            Update,
+            (
+                restart_sampler
+                    .run_if(resource_exists::<Sampler>().and_then(resource_changed::<Params>())),
+                sample.run_if(resource_exists::<Sampler>()),
+                update_plot.run_if(resource_exists_and_changed::<Sampler>()),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The trajectory being sampled, its latest samples and the spectrum they last gave
+#[derive(Resource, Default)]
+struct Sampler {
+    simulation: Option<Simulation>,
+    samples: VecDeque<DVec3>,
+    /// How many samples have come in since the spectrum was last worked out
+    fresh: usize,
+    spectrum: Option<PowerSpectrum>,
+}
+
+#[derive(Component)]
+struct SpectrumPlot;
+
+fn spawn_plot(mut commands: Commands, scene: Res<Scene>) {
+    if !scene.power_spectrum.show {
+        return;
+    }
+
+    commands.init_resource::<Sampler>();
+    commands.spawn((
+        Plot {
+            title: "power spectrum of x (red), y (green) and z (blue)".into(),
+            x_label: "frequency".into(),
+            y_label: "log10 density".into(),
+            ..default()
+        },
+        SpectrumPlot,
+    ));
+}
+
+fn restart_sampler(scene: Res<Scene>, params: Res<Params>, mut sampler: ResMut<Sampler>) {
+    let mut simulation = scene.simulation(&scene.trajectories[0]);
+    simulation.set_params(params.params());
+    for _ in 0..TRANSIENT {
+        simulation.step();
+    }
+    *sampler = Sampler {
+        simulation: Some(simulation),
+        ..default()
+    };
+}
+
+fn sample(scene: Res<Scene>, mut sampler: ResMut<Sampler>) {
+    let config = &scene.power_spectrum;
+    // Only a new estimate changes what is plotted, the samples coming in do not
+    let Sampler {
+        simulation: Some(simulation),
+        samples,
+        fresh,
+        ..
+    } = sampler.bypass_change_detection()
+    else {
+        return;
+    };
+
+    let count = (STEPS_PER_FRAME / config.every).max(1);
+    for _ in 0..count {
+        for _ in 0..config.every {
+            simulation.step();
+        }
+        samples.push_back(simulation.state);
+    }
+    let kept = SEGMENTS * config.segment;
+    if samples.len() > kept {
+        samples.drain(..samples.len() - kept);
+    }
+    *fresh += count;
+    if *fresh < config.segment / 2 {
+        return;
+    }
+
+    *fresh = 0;
+    let interval = simulation.dt * config.every as f64;
+    let spectrum = PowerSpectrum::new(
+        samples.make_contiguous(),
+        interval,
+        config.window,
+        config.segment,
+    );
+    if spectrum.is_some() {
+        sampler.spectrum = spectrum;
+    }
+}
+
+fn update_plot(
+    scene: Res<Scene>,
+    sampler: Res<Sampler>,
+    mut plots: Query<&mut Plot, With<SpectrumPlot>>,
+) {
+    let Ok(mut plot) = plots.get_single_mut() else {
+        return;
+    };
+    let Some(spectrum) = &sampler.spectrum else {
+        plot.series.clear();
+        plot.notes = "waiting for a whole segment of samples".into();
+        return;
+    };
+
+    let bandwidth = spectrum.bandwidth(BANDWIDTH);
+    let colors = [
+        Color::rgb(1., 0.4, 0.4),
+        Color::rgb(0.4, 1., 0.4),
+        Color::rgb(0.4, 0.6, 1.),
+    ];
+    plot.series = colors
+        .into_iter()
+        .enumerate()
+        .map(|(axis, color)| Series {
+            points: spectrum
+                .frequencies()
+                .zip(&spectrum.density)
+                .skip(1)
+                .take_while(|&(f, _)| f <= bandwidth)
+                .filter(|(_, density)| density[axis] > 0.)
+                .map(|(f, density)| Vec2::new(f as f32, density[axis].log10() as f32))
+                .collect(),
+            color,
+            scatter: false,
+        })
+        .collect();
+
+    let peaks = spectrum.peaks();
+    plot.notes = format!(
+        "{} window, {} segments, peaks at {:.3}, {:.3} and {:.3}",
+        scene.power_spectrum.window, spectrum.segments, peaks.x, peaks.y, peaks.z
+    );
+}
@@ -0,0 +1,101 @@
+//! Symbolic dynamics, a trajectory read as the sequence of wings its loops go around
+//!
+//! Every loop of the Lorenz attractor ends at a local maximum of `z`, and the sign of `x` there
+//! tells which wing it went around: `L` for a negative `x` and `R` for a positive one. The
+//! sequence of letters forgets everything but the switching between the wings, and is as
+//! unpredictable as the flow itself. How unpredictable is measured by the block entropy, the
+//! Shannon entropy of the blocks of successive letters, which grows by about the same amount
+//! with every letter added to the blocks once they are long enough.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+};
+
+use glam::DVec3;
+
+use crate::{
+    maxima::{maxima, Maximum},
+    Simulation,
+};
+
+/// One of the two wings of the attractor, either side of the plane `x = 0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Wing {
+    Left,
+    Right,
+}
+
+impl Wing {
+    /// The wing of the point `p`, by the sign of `x`
+    pub fn of(p: DVec3) -> Self {
+        if p.x < 0. {
+            Self::Left
+        } else {
+            Self::Right
+        }
+    }
+
+    /// The letter the wing is written as, `L` or `R`
+    pub fn symbol(self) -> char {
+        match self {
+            Self::Left => 'L',
+            Self::Right => 'R',
+        }
+    }
+}
+
+impl fmt::Display for Wing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char(self.symbol())
+    }
+}
+
+/// The loops over the next `steps` steps of `simulation`, each as the maximum of `z` it ends
+/// at and the wing it went around, as they are found
+pub fn loops(
+    simulation: &mut Simulation,
+    steps: usize,
+) -> impl Iterator<Item = (Maximum, Wing)> + '_ {
+    maxima(simulation, steps).map(|maximum| (maximum, Wing::of(maximum.point)))
+}
+
+/// The Shannon entropy in bits of the blocks of `length` successive wings of `wings`, zero when
+/// there are fewer wings than that
+pub fn block_entropy(wings: &[Wing], length: usize) -> f64 {
+    if length == 0 || wings.len() < length {
+        return 0.;
+    }
+
+    let mut counts: HashMap<&[Wing], usize> = HashMap::new();
+    for block in wings.windows(length) {
+        *counts.entry(block).or_default() += 1;
+    }
+    let total = (wings.len() - length + 1) as f64;
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// How many times the sequence stays on one wing for each number of loops in a row, the count
+/// of runs of `n` loops at index `n`
+///
+/// The first and last runs are left out, the sequence cuts them short.
+pub fn run_lengths(wings: &[Wing]) -> Vec<usize> {
+    let mut histogram = Vec::new();
+    let switches: Vec<usize> = (1..wings.len())
+        .filter(|&i| wings[i] != wings[i - 1])
+        .collect();
+    for pair in switches.windows(2) {
+        let length = pair[1] - pair[0];
+        if histogram.len() <= length {
+            histogram.resize(length + 1, 0);
+        }
+        histogram[length] += 1;
+    }
+    histogram
+}
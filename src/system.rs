@@ -0,0 +1,126 @@
+//! The interface every simulated flow provides
+
+use std::fmt;
+
+use glam::{DMat3, DVec3};
+
+use crate::equilibria;
+
+/// The number of variables in the state of every system, they are all flows in three
+/// dimensions held in a [`DVec3`]
+pub const DIMENSION: usize = 3;
+
+/// Where the camera starts out looking at a system from, and how far it extends
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Framing {
+    pub position: DVec3,
+    pub look_at: DVec3,
+    /// The range `x` roughly covers on the attractor
+    pub x: [f64; 2],
+    /// The range `y` roughly covers on the attractor
+    pub y: [f64; 2],
+}
+
+impl Framing {
+    /// A view of everything in `points`, or `None` when there are no points
+    pub fn fit(points: impl IntoIterator<Item = DVec3>) -> Option<Framing> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let (min, max) = points.fold((first, first), |(min, max), p| (min.min(p), max.max(p)));
+
+        let center = (min + max) / 2.;
+        let span = max - min;
+        // Even a single point gets a view with some room around it
+        let size = match span.max_element() {
+            size if size > 0. => size,
+            _ => 1.,
+        };
+        // Spread the colour map over the middle of each range
+        let range = |center: f64, span: f64| {
+            let half = 0.35 * span.max(0.1 * size);
+            [center - half, center + half]
+        };
+
+        Some(Framing {
+            position: center + DVec3::new(-2., 0., 3.) * size,
+            look_at: center,
+            x: range(center.x, span.x),
+            y: range(center.y, span.y),
+        })
+    }
+}
+
+/// An autonomous system of ordinary differential equations `dp/dt = f(p)`
+pub trait DynamicalSystem: fmt::Debug + Send + Sync {
+    /// The short lowercase name the system is picked by
+    fn name(&self) -> &str;
+
+    /// The name of the parameter at `index` into [`DynamicalSystem::params`]
+    fn param_name(&self, index: usize) -> &str;
+
+    fn params(&self) -> &[f64];
+
+    fn params_mut(&mut self) -> &mut [f64];
+
+    /// The rate of change of the flow at a point
+    fn derivative(&self, p: DVec3) -> DVec3;
+
+    /// The matrix of partial derivatives of [`DynamicalSystem::derivative`] at a point, each
+    /// column holding the derivatives with respect to one of `x`, `y` and `z`
+    ///
+    /// Found by central differences unless a system knows it exactly.
+    fn jacobian(&self, p: DVec3) -> DMat3 {
+        numerical_jacobian(|p| self.derivative(p), p)
+    }
+
+    /// The points where the flow stands still
+    ///
+    /// Searched for with Newton's method unless a system knows them exactly, see
+    /// [`equilibria::search`].
+    fn equilibria(&self) -> Vec<DVec3> {
+        equilibria::search(
+            |p| self.derivative(p),
+            |p| self.jacobian(p),
+            &self.framing(),
+        )
+    }
+
+    /// A point that soon settles onto the attractor
+    fn initial_state(&self) -> DVec3;
+
+    /// A time step small enough for the fixed step integrators to follow the flow closely
+    fn dt(&self) -> f64 {
+        0.001
+    }
+
+    fn framing(&self) -> Framing;
+
+    fn clone_box(&self) -> Box<dyn DynamicalSystem>;
+
+    /// The index of the parameter called `name`
+    fn param_index(&self, name: &str) -> Option<usize> {
+        (0..self.params().len()).find(|&index| self.param_name(index) == name)
+    }
+
+    /// The names of every parameter, in order
+    fn param_names(&self) -> Vec<&str> {
+        (0..self.params().len())
+            .map(|index| self.param_name(index))
+            .collect()
+    }
+}
+
+/// The Jacobian of `f` at `p` by central differences, with steps scaled to each coordinate
+pub fn numerical_jacobian(f: impl Fn(DVec3) -> DVec3, p: DVec3) -> DMat3 {
+    let column = |axis: DVec3| {
+        let h = 1e-6 * p.dot(axis).abs().max(1.);
+        (f(p + axis * h) - f(p - axis * h)) / (2. * h)
+    };
+    DMat3::from_cols(column(DVec3::X), column(DVec3::Y), column(DVec3::Z))
+}
+
+impl Clone for Box<dyn DynamicalSystem> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
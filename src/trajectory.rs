@@ -0,0 +1,144 @@
+//! A trajectory drawn as one growing line strip
+//!
+//! Points are pushed onto a [`Trajectory`] during `Update` and appended to its mesh in
+//! `PostUpdate`. The line is split into fixed-size sub-meshes so that appending only ever
+//! re-uploads the chunk that is currently being written to, no matter how long the run is.
+
+use bevy::{
+    prelude::*,
+    render::{mesh::VertexAttributeValues, view::NoFrustumCulling},
+};
+
+use crate::{LineMaterial, LineStrip};
+
+/// Maximum number of points held by a single sub-mesh
+const CHUNK_POINTS: usize = 1 << 16;
+
+pub struct TrajectoryPlugin;
+
+impl Plugin for TrajectoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, flush_trajectories);
+    }
+}
+
+/// A per-vertex coloured line strip that can be appended to in place
+///
+/// The entity holding this component should also have a [`SpatialBundle`], the sub-meshes
+/// are spawned as its children.
+#[derive(Component)]
+pub struct Trajectory {
+    material: Handle<LineMaterial>,
+    pending: Vec<(Vec3, Color)>,
+    chunk: Option<Handle<Mesh>>,
+    chunk_len: usize,
+    last: Option<(Vec3, Color)>,
+    cleared: bool,
+}
+
+impl Trajectory {
+    pub fn new(material: Handle<LineMaterial>) -> Self {
+        Self {
+            material,
+            pending: Vec::new(),
+            chunk: None,
+            chunk_len: 0,
+            last: None,
+            cleared: false,
+        }
+    }
+
+    /// Removes every point, including those that have already been drawn
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.chunk = None;
+        self.chunk_len = 0;
+        self.last = None;
+        self.cleared = true;
+    }
+
+    /// Queues a point to be added to the end of the line
+    pub fn push(&mut self, point: Vec3, color: Color) {
+        self.pending.push((point, color));
+    }
+}
+
+fn flush_trajectories(
+    mut commands: Commands,
+    mut trajectories: Query<(Entity, &mut Trajectory)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (entity, trajectory) in &mut trajectories {
+        if trajectory.pending.is_empty() && !trajectory.cleared {
+            continue;
+        }
+        let trajectory = trajectory.into_inner();
+
+        // The old sub-meshes are despawned before any new ones are spawned below, as the
+        // commands are applied in order
+        if trajectory.cleared {
+            commands.entity(entity).despawn_descendants();
+            trajectory.cleared = false;
+        }
+
+        let pending = std::mem::take(&mut trajectory.pending);
+        let mut remaining = &pending[..];
+
+        while !remaining.is_empty() {
+            let chunk = match &trajectory.chunk {
+                Some(chunk) if trajectory.chunk_len < CHUNK_POINTS => chunk.clone(),
+                _ => {
+                    // Start the new chunk on the last point of the previous one so the
+                    // line stays connected across sub-meshes
+                    let (points, colors): (Vec<_>, Vec<_>) = trajectory.last.into_iter().unzip();
+                    trajectory.chunk_len = points.len();
+
+                    let chunk = meshes.add(Mesh::from(LineStrip {
+                        points,
+                        colors: Some(colors),
+                    }));
+                    let material = trajectory.material.clone();
+                    commands.entity(entity).with_children(|parent| {
+                        parent.spawn((
+                            MaterialMeshBundle {
+                                mesh: chunk.clone(),
+                                material,
+                                ..default()
+                            },
+                            // The bounds are only computed once when the mesh is spawned,
+                            // so they would be wrong as soon as the line grows
+                            NoFrustumCulling,
+                        ));
+                    });
+                    trajectory.chunk = Some(chunk.clone());
+                    chunk
+                }
+            };
+
+            let count = remaining.len().min(CHUNK_POINTS - trajectory.chunk_len);
+            let (points, rest) = remaining.split_at(count);
+
+            let mesh = meshes
+                .get_mut(&chunk)
+                .expect("trajectory chunk mesh should exist");
+            if let Some(VertexAttributeValues::Float32x3(positions)) =
+                mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+            {
+                positions.extend(points.iter().map(|(point, _)| point.to_array()));
+            }
+            if let Some(VertexAttributeValues::Float32x4(colors)) =
+                mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR)
+            {
+                colors.extend(points.iter().map(|(_, color)| color.as_linear_rgba_f32()));
+            }
+
+            trajectory.chunk_len += count;
+            trajectory.last = points.last().copied();
+            remaining = rest;
+        }
+
+        // Hand the buffer back so its capacity is reused next frame
+        trajectory.pending = pending;
+        trajectory.pending.clear();
+    }
+}
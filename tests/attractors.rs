@@ -0,0 +1,49 @@
+use lorenz_attractor::{system::numerical_jacobian, Attractor, DynamicalSystem, Simulation};
+
+#[test]
+fn every_attractor_stays_in_view() {
+    for system in Attractor::all() {
+        let name = system.name().to_owned();
+        let framing = system.framing();
+        // Generously more than the distance between the camera and what it looks at
+        let bound = 2. * framing.position.distance(framing.look_at);
+
+        let initial = system.initial_state();
+        let mut simulation = Simulation::new(Box::new(system), initial);
+        for _ in 0..100_000 {
+            let p = simulation.step();
+            assert!(
+                p.is_finite() && p.distance(framing.look_at) < bound,
+                "{name} left the view at t = {}: {p}",
+                simulation.time
+            );
+        }
+    }
+}
+
+#[test]
+fn names_pick_out_the_attractors() {
+    for name in Attractor::names() {
+        let system: Attractor = name.parse().unwrap();
+        assert_eq!(system.name(), name);
+        assert_eq!(system.params().len(), system.param_names().len());
+    }
+    assert!("lorenz63".parse::<Attractor>().is_err());
+}
+
+#[test]
+fn lorenz_jacobian_matches_central_differences() {
+    let system = Attractor::lorenz();
+    for p in [
+        system.initial_state(),
+        glam::DVec3::new(-8., 5., 30.),
+        glam::DVec3::new(12., 20., 2.),
+    ] {
+        let exact = system.jacobian(p);
+        let approximate = numerical_jacobian(|p| system.derivative(p), p);
+        assert!(
+            exact.abs_diff_eq(approximate, 1e-6),
+            "at {p}: {exact} against {approximate}"
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use glam::DVec3;
+use lorenz_attractor::{
+    bifurcation::{rasterise, Column, Method, Sweep},
+    poincare::{Direction, Plane, Section},
+    Attractor, DynamicalSystem, Simulation,
+};
+
+/// The classic Lorenz system, swept over `rho` from `from` to `to`
+fn lorenz(from: f64, to: f64, count: usize) -> (Simulation, Sweep) {
+    let system = Attractor::lorenz();
+    let param = system.param_index("rho").unwrap();
+    let initial = system.initial_state();
+    let mut simulation = Simulation::new(Box::new(system), initial);
+    simulation.dt = 0.005;
+    let sweep = Sweep {
+        param,
+        from,
+        to,
+        count,
+        transient: 20_000,
+        steps: 40_000,
+    };
+    (simulation, sweep)
+}
+
+/// The section through the two fixed points of the Lorenz system, at `z = rho - 1`
+fn section(system: &dyn DynamicalSystem) -> Result<Section, String> {
+    let rho = system.params()[system.param_index("rho").unwrap()];
+    Ok(Section {
+        plane: Plane::new(DVec3::Z, rho - 1.),
+        direction: Direction::Down,
+    })
+}
+
+/// How many different values `column` holds, to three decimals
+fn distinct(column: &Column) -> usize {
+    let mut values: Vec<_> = column
+        .values
+        .iter()
+        .map(|value| (value * 1000.).round() as i64)
+        .collect();
+    values.sort_unstable();
+    values.dedup();
+    values.len()
+}
+
+#[test]
+fn the_parameter_is_swept_in_order_with_both_ends() {
+    let (_, sweep) = lorenz(20., 30., 11);
+    let params: Vec<_> = sweep.params().collect();
+    assert_eq!(params.len(), 11);
+    assert_eq!(params[0], 20.);
+    assert_eq!(params[10], 30.);
+    assert!(params
+        .windows(2)
+        .all(|pair| (pair[1] - pair[0] - 1.).abs() < 1e-12));
+}
+
+#[test]
+fn chaos_smears_and_a_periodic_window_does_not() {
+    // rho = 160 lies in the wide periodic window above 145
+    let (simulation, sweep) = lorenz(28., 160., 2);
+
+    for method in Method::ALL {
+        let columns = sweep.run(&simulation, method, section).unwrap();
+        assert_eq!(columns.len(), 2);
+        let (chaotic, periodic) = (&columns[0], &columns[1]);
+        assert_eq!((chaotic.param, periodic.param), (28., 160.));
+
+        assert!(
+            distinct(chaotic) > 100,
+            "{method}: only {} values at rho = 28",
+            distinct(chaotic)
+        );
+        assert!(
+            (1..=4).contains(&distinct(periodic)),
+            "{method}: {} values at rho = 160",
+            distinct(periodic)
+        );
+    }
+}
+
+#[test]
+fn the_diagram_is_dark_where_the_values_fall() {
+    let columns = [
+        Column {
+            param: 0.,
+            values: vec![0.; 10],
+        },
+        Column {
+            param: 1.,
+            values: vec![1.],
+        },
+    ];
+    let pixels = rasterise(&columns, (0., 1.), 3, 2);
+    // The highest value is on the top row and the most visited pixel is black
+    assert_eq!(pixels[..2], [255, 255]);
+    assert_eq!(pixels[3..], [0, 255, 255]);
+    // One seen once is a grey that still shows
+    assert!((50..200).contains(&pixels[2]), "{pixels:?}");
+}
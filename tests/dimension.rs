@@ -0,0 +1,70 @@
+use glam::DVec3;
+use lorenz_attractor::{
+    dimension::{radii, CorrelationIntegral, KdTree},
+    Attractor, DynamicalSystem, Simulation,
+};
+
+/// `count` points of the classic Lorenz attractor, `every` steps apart
+fn lorenz(count: usize, every: usize) -> Vec<DVec3> {
+    let system = Attractor::lorenz();
+    let mut simulation = Simulation::new(Box::new(system.clone()), system.initial_state());
+    for _ in 0..10_000 {
+        simulation.step();
+    }
+    (0..count)
+        .map(|_| {
+            for _ in 0..every {
+                simulation.step();
+            }
+            simulation.state
+        })
+        .collect()
+}
+
+#[test]
+fn the_tree_finds_the_same_points_as_checking_them_all() {
+    let points = lorenz(5000, 7);
+    let tree = KdTree::new(&points);
+    assert_eq!(tree.len(), points.len());
+
+    for (center, radius) in [(points[0], 2.), (points[1234], 5.), (DVec3::ZERO, 10.)] {
+        let mut found = Vec::new();
+        tree.within(center, radius, |index, distance| {
+            assert!((distance - points[index].distance(center)).abs() < 1e-12);
+            found.push(index);
+        });
+        found.sort();
+        let expected: Vec<_> = (0..points.len())
+            .filter(|&index| points[index].distance(center) < radius)
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(found, expected);
+    }
+}
+
+#[test]
+fn points_spread_over_a_square_are_two_dimensional() {
+    // The golden ratio sequence fills the square evenly without a lattice of its own
+    let points: Vec<_> = (0..20_000)
+        .map(|i| {
+            let i = i as f64;
+            DVec3::new((i * 0.754_877_666).fract(), (i * 0.569_840_291).fract(), 0.)
+        })
+        .collect();
+    let integral = CorrelationIntegral::new(&points, radii(0.005, 0.05, 20), 0);
+    assert_eq!(integral.total, 20_000 * 19_999 / 2);
+
+    let fit = integral.fit(10).unwrap();
+    assert!((fit.dimension - 2.).abs() < 0.1, "{fit:?}");
+}
+
+#[test]
+fn the_lorenz_attractor_has_a_correlation_dimension_of_about_two() {
+    let points = lorenz(100_000, 10);
+    let integral = CorrelationIntegral::new(&points, radii(0.08, 2.5, 30), 100);
+    assert!(integral.pairs.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    // Grassberger and Procaccia found 2.05
+    let fit = integral.fit(12).unwrap();
+    assert!((fit.dimension - 2.05).abs() < 0.05, "{fit:?}");
+}
@@ -0,0 +1,45 @@
+use glam::DVec3;
+use lorenz_attractor::{
+    divergence::{fit_growth, Divergence},
+    Attractor, DynamicalSystem, Simulation,
+};
+
+#[test]
+fn the_rate_of_a_saturating_exponential_is_recovered() {
+    // Grows as e^{0.9 t} from 1e-9 until it levels off at the size of an attractor
+    let samples: Vec<(f64, f64)> = (0..=4000)
+        .map(|i| {
+            let t = i as f64 * 0.01;
+            (t, (1e-9 * (0.9 * t).exp()).min(20.))
+        })
+        .collect();
+
+    let fit = fit_growth(&samples).unwrap();
+    assert!((fit.rate - 0.9).abs() < 1e-9, "fitted {}", fit.rate);
+    assert!((fit.initial - 1e-9).abs() < 1e-15, "fitted {}", fit.initial);
+    assert!(fit.evaluate(fit.until) < 0.2, "fitted until {}", fit.until);
+}
+
+#[test]
+fn nothing_is_fitted_without_growth() {
+    assert_eq!(fit_growth(&[]), None);
+    assert_eq!(fit_growth(&[(0., 1.), (1., 1.), (2., 1.)]), None);
+}
+
+#[test]
+fn a_lorenz_pair_parts_at_about_the_largest_lyapunov_exponent() {
+    let system = Attractor::lorenz();
+    let initial = system.initial_state();
+    let mut simulation = Simulation::new(Box::new(system), initial);
+    simulation.dt = 0.01;
+    for _ in 0..10_000 {
+        simulation.step();
+    }
+
+    let mut divergence = Divergence::new(simulation, DVec3::X * 1e-10);
+    let samples: Vec<_> = divergence.samples(5000).collect();
+    divergence.check().unwrap();
+
+    let fit = fit_growth(&samples).unwrap();
+    assert!((fit.rate - 0.9).abs() < 0.3, "fitted {}", fit.rate);
+}
@@ -0,0 +1,81 @@
+use glam::DVec3;
+use lorenz_attractor::{
+    equations::EquationError, expr::Expr, Attractor, DynamicalSystem, Equations,
+};
+
+fn lorenz(equations: &[&str]) -> Result<Equations, EquationError> {
+    let params = [("s", 10.), ("r", 28.), ("b", 8. / 3.)].map(|(name, value)| (name.into(), value));
+    Equations::new(equations, &params)
+}
+
+#[test]
+fn typed_in_equations_match_the_built_in_system() {
+    let equations =
+        lorenz(&["dx = s * (y - x)", "dz = x*y - b*z", "dy = x * (r - z) - y"]).unwrap();
+    let builtin = Attractor::lorenz();
+
+    for p in [DVec3::new(1., 1., 1.), DVec3::new(-3.5, 7., 20.)] {
+        assert_eq!(equations.derivative(p), builtin.derivative(p));
+    }
+}
+
+#[test]
+fn expressions_follow_the_usual_precedence() {
+    let p = DVec3::new(2., 3., 0.5);
+    for (source, expected) in [
+        ("1 + 2 * 3", 7.),
+        ("-x^2", -4.),
+        ("2^3^2", 512.),
+        ("(x + y) / 2", 2.5),
+        ("atan2(0, -1) - pi", 0.),
+        ("max(x, y) * sqrt(4) + 1e-1", 6.1),
+        ("k * z", 1.5),
+    ] {
+        let expr = Expr::parse(source, &["k"]).unwrap();
+        assert_eq!(expr.evaluate(p, &[3.]), expected, "{source}");
+    }
+}
+
+#[test]
+fn errors_point_at_the_mistake() {
+    for (equations, column, message) in [
+        (
+            ["dx = s * (y - x)", "dy = x * (q - z) - y", "dz = b"],
+            11,
+            "unknown variable `q`",
+        ),
+        (["dx = s * (y - x", "dy = r", "dz = b"], 16, "expected `)`"),
+        (
+            ["dx = s * $", "dy = r", "dz = b"],
+            10,
+            "unexpected character `$`",
+        ),
+        (
+            ["dx = s", "dy = r", "d = b"],
+            1,
+            "expected `dx`, `dy` or `dz`",
+        ),
+        (
+            ["dx = s", "dy = r", "dz = cos(b, x)"],
+            6,
+            "`cos` takes 1 argument",
+        ),
+    ] {
+        match lorenz(&equations) {
+            Err(EquationError::Syntax { error, .. }) => {
+                assert_eq!(error.position + 1, column, "{error}");
+                assert!(error.message.starts_with(message), "{error}");
+            }
+            other => panic!("{equations:?} gave {other:?}"),
+        }
+    }
+
+    assert_eq!(
+        lorenz(&["dx = s", "dy = r"]).unwrap_err(),
+        EquationError::Missing("dz")
+    );
+    assert_eq!(
+        lorenz(&["dx = s", "dy = r", "dz = x"]).unwrap_err(),
+        EquationError::Unused("b".into())
+    );
+}
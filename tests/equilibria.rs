@@ -0,0 +1,136 @@
+use glam::{DMat3, DVec3};
+use lorenz_attractor::{
+    equilibria::{eigenvalues, equilibria, Equilibrium, Kind},
+    Attractor, DynamicalSystem, Equations,
+};
+
+/// The Lorenz system with `rho` in place of the usual 28
+fn lorenz(rho: f64) -> Attractor {
+    let mut system = Attractor::lorenz();
+    let index = system.param_index("rho").unwrap();
+    system.params_mut()[index] = rho;
+    system
+}
+
+/// Finds the equilibrium closest to `point`
+fn closest(equilibria: &[Equilibrium], point: DVec3) -> Equilibrium {
+    *equilibria
+        .iter()
+        .min_by(|a, b| a.point.distance(point).total_cmp(&b.point.distance(point)))
+        .unwrap()
+}
+
+#[test]
+fn the_lorenz_attractor_winds_between_three_saddles() {
+    let found = equilibria(&lorenz(28.));
+    assert_eq!(found.len(), 3);
+
+    let origin = closest(&found, DVec3::ZERO);
+    assert_eq!(origin.point, DVec3::ZERO);
+    assert_eq!(origin.kind, Kind::Saddle { spiral: false });
+    // The eigenvalues at the origin are known in closed form
+    let (sigma, rho, beta) = (10., 28., 8. / 3.);
+    let root = ((sigma + 1f64).powi(2) + 4. * sigma * (rho - 1.)).sqrt();
+    let expected = [(root - sigma - 1.) / 2., -beta, (-root - sigma - 1.) / 2.];
+    for (eigenvalue, expected) in origin.eigenvalues.iter().zip(expected) {
+        assert!((eigenvalue.re - expected).abs() < 1e-9, "{eigenvalue}");
+        assert_eq!(eigenvalue.im, 0.);
+    }
+
+    let r = (beta * (rho - 1.)).sqrt();
+    for side in [1., -1.] {
+        let wing = closest(&found, DVec3::new(side * r, side * r, rho - 1.));
+        assert!(
+            wing.point
+                .distance(DVec3::new(side * r, side * r, rho - 1.))
+                < 1e-12,
+            "{wing:?}"
+        );
+        assert_eq!(wing.kind, Kind::Saddle { spiral: true });
+        // A slowly unwinding spiral and a quickly attracting direction, as Lorenz found
+        let [spiral, conjugate, attracting] = wing.eigenvalues;
+        assert!((spiral.re - 0.0940).abs() < 1e-3, "{spiral}");
+        assert!((spiral.im - 10.1945).abs() < 1e-3, "{spiral}");
+        assert_eq!(conjugate.im, -spiral.im);
+        assert!((attracting.re + 13.8546).abs() < 1e-3, "{attracting}");
+    }
+}
+
+#[test]
+fn the_stability_follows_rho() {
+    let kinds = |rho| {
+        let mut kinds: Vec<_> = equilibria(&lorenz(rho))
+            .into_iter()
+            .map(|equilibrium| equilibrium.kind.to_string())
+            .collect();
+        kinds.sort();
+        kinds
+    };
+    assert_eq!(kinds(0.5), ["stable node"]);
+    assert_eq!(kinds(1.2), ["saddle", "stable node", "stable node"]);
+    assert_eq!(kinds(10.), ["saddle", "stable spiral", "stable spiral"]);
+    assert_eq!(kinds(28.), ["saddle", "spiral saddle", "spiral saddle"]);
+}
+
+#[test]
+fn newton_finds_the_equilibria_of_typed_in_equations() {
+    let params = [("s", 10.), ("r", 28.), ("b", 8. / 3.)].map(|(name, value)| (name.into(), value));
+    let equations = Equations::new(
+        &[
+            "dx = s * (y - x)",
+            "dy = x * (r - z) - y",
+            "dz = x * y - b * z",
+        ],
+        &params,
+    )
+    .unwrap();
+
+    let exact = equilibria(&lorenz(28.));
+    let found = equilibria(&equations);
+    assert_eq!(found.len(), exact.len(), "{found:?}");
+    for equilibrium in exact {
+        let searched = closest(&found, equilibrium.point);
+        assert!(
+            searched.point.distance(equilibrium.point) < 1e-8,
+            "{searched:?}"
+        );
+        assert_eq!(searched.kind, equilibrium.kind);
+    }
+}
+
+#[test]
+fn eigenvalues_of_known_matrices() {
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+
+    let diagonal = DMat3::from_diagonal(DVec3::new(-1., 3., 2.));
+    let found = eigenvalues(diagonal).map(|eigenvalue| eigenvalue.re);
+    assert!(close(found[0], 3.) && close(found[1], 2.) && close(found[2], -1.));
+    assert_eq!(
+        Kind::of(&eigenvalues(diagonal)),
+        Kind::Saddle { spiral: false }
+    );
+
+    // A rotation about z shrinking towards the origin
+    let rotation = DMat3::from_cols(
+        DVec3::new(-0.5, 2., 0.),
+        DVec3::new(-2., -0.5, 0.),
+        DVec3::new(0., 0., -1.),
+    );
+    let [first, second, third] = eigenvalues(rotation);
+    assert!(close(first.re, -0.5) && close(first.im, 2.), "{first}");
+    assert!(close(second.re, -0.5) && close(second.im, -2.), "{second}");
+    assert!(close(third.re, -1.) && third.im == 0., "{third}");
+    assert_eq!(
+        Kind::of(&eigenvalues(rotation)),
+        Kind::Spiral { stable: true }
+    );
+
+    let repeated = DMat3::IDENTITY * 2.;
+    assert!(eigenvalues(repeated)
+        .iter()
+        .all(|eigenvalue| close(eigenvalue.re, 2.)));
+    assert_eq!(
+        Kind::of(&eigenvalues(repeated)),
+        Kind::Node { stable: false }
+    );
+}
@@ -0,0 +1,83 @@
+use lorenz_attractor::{
+    export::{Format, Header, Recording, SampleWriter, NATIVE_VERSION},
+    Attractor, DynamicalSystem, Integrator, Simulation,
+};
+
+/// A short trajectory written out in `format`, with the header it was written with
+fn written(format: Format) -> (Vec<u8>, Header, Vec<(f64, glam::DVec3)>) {
+    let system = Attractor::lorenz();
+    let initial = system.initial_state();
+    let mut simulation = Simulation::new(Box::new(system), initial);
+    simulation.integrator = Integrator::DormandPrince;
+    simulation.dt = 0.005;
+
+    let samples: Vec<_> = (0..100)
+        .map(|_| {
+            simulation.step();
+            (simulation.time, simulation.state)
+        })
+        .collect();
+    let header = Header::new(&simulation, samples.len() as u64);
+    let mut writer = SampleWriter::new(Vec::new(), format, &header).unwrap();
+    for &(t, p) in &samples {
+        writer.write(t, p).unwrap();
+    }
+    (writer.finish().unwrap(), header, samples)
+}
+
+#[test]
+fn binary_formats_read_back_exactly() {
+    let (bytes, header, samples) = written(Format::Npy);
+    let recording = Recording::read(&bytes[..]).unwrap();
+    assert_eq!(recording.header, None);
+    assert_eq!(recording.samples, samples);
+
+    let (bytes, header_written, _) = written(Format::Binary);
+    assert_eq!(header_written, header);
+    let recording = Recording::read(&bytes[..]).unwrap();
+    let read = recording.header.unwrap();
+    assert_eq!(read, header);
+    assert_eq!(read.system, "lorenz");
+    assert_eq!(
+        read.params,
+        [("sigma", 10.), ("rho", 28.), ("beta", 8. / 3.)]
+            .map(|(name, value)| (name.to_owned(), value))
+    );
+    assert_eq!(read.integrator, Integrator::DormandPrince);
+    assert_eq!(read.dt, 0.005);
+    assert_eq!(read.samples, 100);
+    assert_eq!(recording.samples, samples);
+}
+
+#[test]
+fn truncated_files_are_rejected() {
+    for format in [Format::Npy, Format::Binary] {
+        let (bytes, _, _) = written(format);
+        for length in [2, 20, bytes.len() - 1] {
+            assert!(
+                Recording::read(&bytes[..length]).is_err(),
+                "{format} cut to {length} bytes was read"
+            );
+        }
+    }
+}
+
+#[test]
+fn files_of_another_kind_are_rejected() {
+    for format in [Format::Npy, Format::Binary] {
+        let (mut bytes, _, _) = written(format);
+        bytes[1] = b'?';
+        let error = Recording::read(&bytes[..]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{format}");
+    }
+
+    // A native file from a later version of the format
+    let (mut bytes, _, _) = written(Format::Binary);
+    let version = NATIVE_VERSION + 1;
+    bytes[4..8].copy_from_slice(&version.to_le_bytes());
+    let error = Recording::read(&bytes[..]).unwrap_err();
+    assert!(
+        error.to_string().contains(&format!("version {version}")),
+        "{error}"
+    );
+}
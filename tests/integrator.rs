@@ -0,0 +1,150 @@
+use glam::{DVec3, Vec3};
+use lorenz_attractor::{Attractor, DynamicalSystem, Equations, Integrator, Simulation};
+
+const INITIAL: DVec3 = DVec3::new(1., 1., 1.);
+const DURATION: f64 = 1.;
+
+/// Integrates the classic Lorenz system with a tiny RK4 step
+fn reference() -> DVec3 {
+    let lorenz = Attractor::lorenz();
+    let f = |p| lorenz.derivative(p);
+
+    let dt = 1e-5;
+    let mut p = INITIAL;
+    for _ in 0..(DURATION / dt).round() as usize {
+        let k1 = f(p);
+        let k2 = f(p + k1 * (dt / 2.));
+        let k3 = f(p + k2 * (dt / 2.));
+        let k4 = f(p + k3 * dt);
+        p += (k1 + 2. * k2 + 2. * k3 + k4) * (dt / 6.);
+    }
+    p
+}
+
+fn error(integrator: Integrator, dt: f64) -> f64 {
+    let mut lorenz = Simulation::new(Box::new(Attractor::lorenz()), INITIAL);
+    lorenz.integrator = integrator;
+    lorenz.dt = dt;
+
+    for _ in 0..(DURATION / dt).round() as usize {
+        lorenz.step();
+    }
+
+    lorenz.state.distance(reference())
+}
+
+#[test]
+fn integrators_track_the_reference() {
+    for (integrator, tolerance) in [
+        (Integrator::Euler, 1.),
+        (Integrator::Midpoint, 1e-3),
+        (Integrator::RungeKutta4, 1e-7),
+        (Integrator::DormandPrince, 1e-7),
+    ] {
+        let error = error(integrator, 0.001);
+        assert!(
+            error < tolerance,
+            "{integrator} drifted {error} from the reference"
+        );
+    }
+}
+
+#[test]
+fn higher_order_integrators_are_more_accurate() {
+    let errors: Vec<_> = Integrator::ALL
+        .into_iter()
+        .map(|integrator| error(integrator, 0.005))
+        .collect();
+
+    assert!(
+        errors.windows(2).all(|pair| pair[1] < pair[0]),
+        "{errors:?}"
+    );
+}
+
+#[test]
+fn error_shrinks_with_the_order_of_the_integrator() {
+    for integrator in Integrator::ALL.into_iter().filter(|i| !i.is_adaptive()) {
+        let ratio = error(integrator, 0.002) / error(integrator, 0.001);
+        let expected = 2f64.powi(integrator.order());
+        assert!(
+            (0.75 * expected..1.25 * expected).contains(&ratio),
+            "{integrator} error ratio was {ratio}, expected about {expected}"
+        );
+    }
+}
+
+#[test]
+fn adaptive_integrator_tracks_the_reference() {
+    let mut lorenz = Simulation::new(Box::new(Attractor::lorenz()), INITIAL);
+    lorenz.integrator = Integrator::DormandPrince;
+    lorenz.dt = 0.001;
+
+    for _ in 0..1000 {
+        lorenz.step();
+    }
+
+    let error = lorenz.state.distance(reference());
+    let stats = lorenz.adaptive.stats;
+    assert!(error < 1e-7, "rk45 drifted {error} from the reference");
+
+    // The samples are much closer together than the steps the solver needs to take
+    assert!(stats.accepted < 500, "{stats:?}");
+}
+
+#[test]
+fn double_precision_agrees_better_than_single() {
+    // The same RK4 step the simulation used to take on an f32 state
+    let f = |p: Vec3| {
+        Vec3::new(
+            10. * (p.y - p.x),
+            p.x * (28. - p.z) - p.y,
+            p.x * p.y - 8. / 3. * p.z,
+        )
+    };
+    let dt = 0.001f32;
+    let mut single = INITIAL.as_vec3();
+    for _ in 0..(DURATION / dt as f64).round() as usize {
+        let k1 = f(single);
+        let k2 = f(single + k1 * (dt / 2.));
+        let k3 = f(single + k2 * (dt / 2.));
+        let k4 = f(single + k3 * dt);
+        single += (k1 + 2. * k2 + 2. * k3 + k4) * (dt / 6.);
+    }
+    let single_error = single.as_dvec3().distance(reference());
+
+    let double_error = error(Integrator::RungeKutta4, 0.001);
+
+    assert!(
+        double_error * 1000. < single_error,
+        "f64 error {double_error} is not much smaller than f32 error {single_error}"
+    );
+}
+
+#[test]
+fn a_solution_blowing_up_stops_the_simulation() {
+    // x = 1 / (1 - t) goes to infinity at t = 1
+    let equations = Equations::new(&["dx = x * x", "dy = 0", "dz = 0"], &[]).unwrap();
+    for integrator in [Integrator::RungeKutta4, Integrator::DormandPrince] {
+        let mut simulation = Simulation::new(Box::new(equations.clone()), DVec3::X);
+        simulation.dt = 0.001;
+        simulation.integrator = integrator;
+
+        let samples = simulation.samples(2000).count();
+        let failure = simulation.check().unwrap_err();
+        assert!(failure.contains("t = "), "{integrator}: {failure}");
+        assert!(samples < 1100, "{integrator}: {samples}");
+        assert!(
+            (0.9..1.1).contains(&simulation.time),
+            "{integrator}: {failure}"
+        );
+        assert!(simulation.state.is_finite());
+
+        // It stays stopped until it is started again
+        let stopped = (simulation.time, simulation.state);
+        simulation.step();
+        assert_eq!((simulation.time, simulation.state), stopped);
+        simulation.restart(DVec3::X);
+        assert!(simulation.check().is_ok());
+    }
+}
@@ -0,0 +1,51 @@
+use lorenz_attractor::{
+    lyapunov::{kaplan_yorke_dimension, Lyapunov, Spectrum},
+    Attractor, DynamicalSystem, Simulation,
+};
+
+/// The classic Lorenz system with a coarse time step, settled onto the attractor
+fn settled_lorenz() -> Simulation {
+    let system = Attractor::lorenz();
+    let initial = system.initial_state();
+    let mut simulation = Simulation::new(Box::new(system), initial);
+    simulation.dt = 0.01;
+    for _ in 0..10_000 {
+        simulation.step();
+    }
+    simulation
+}
+
+#[test]
+fn classic_lorenz_has_the_known_largest_exponent() {
+    let mut lyapunov = Lyapunov::new(settled_lorenz(), 1e-8, 10);
+    let exponent = lyapunov.run(200_000).unwrap();
+    assert!(
+        (exponent - 0.906).abs() < 0.03,
+        "estimated {exponent}, expected about 0.906"
+    );
+}
+
+#[test]
+fn classic_lorenz_has_the_known_spectrum() {
+    let mut spectrum = Spectrum::new(settled_lorenz(), 10);
+    let exponents = spectrum.run(200_000).unwrap();
+
+    for (exponent, expected) in exponents.into_iter().zip([0.906, 0., -14.572]) {
+        assert!(
+            (exponent - expected).abs() < 0.03,
+            "estimated {exponents:?}, expected about {expected}"
+        );
+    }
+    // Volumes shrink at the rate the trace of the Jacobian gives, -(σ + 1 + β)
+    let sum: f64 = exponents.iter().sum();
+    assert!(
+        (sum + 41. / 3.).abs() < 1e-3,
+        "the exponents add up to {sum}"
+    );
+
+    let dimension = kaplan_yorke_dimension(&exponents);
+    assert!(
+        (dimension - 2.062).abs() < 0.005,
+        "estimated a dimension of {dimension}"
+    );
+}
@@ -0,0 +1,73 @@
+use lorenz_attractor::{
+    maxima::{maxima, maximum, return_map},
+    Attractor, DynamicalSystem, Simulation,
+};
+
+/// The classic Lorenz system with the time step `dt`, settled onto the attractor
+fn lorenz(dt: f64) -> Simulation {
+    let system = Attractor::lorenz();
+    let initial = system.initial_state();
+    let mut simulation = Simulation::new(Box::new(system), initial);
+    simulation.dt = dt;
+    for _ in 0..(20. / dt) as usize {
+        simulation.step();
+    }
+    simulation
+}
+
+#[test]
+fn maxima_are_pinned_down_within_the_step() {
+    let mut coarse = lorenz(0.01);
+
+    let mut checked = 0;
+    while checked < 10 {
+        let from = (coarse.time, coarse.state);
+        coarse.step();
+        let Some(found) = maximum(&*coarse.system, from, (coarse.time, coarse.state)) else {
+            continue;
+        };
+
+        // The same step again in a thousand smaller ones
+        let mut fine = coarse.clone();
+        fine.dt = 0.00001;
+        (fine.time, fine.state) = from;
+        let fine: Vec<_> = maxima(&mut fine, 1000).collect();
+        assert_eq!(fine.len(), 1);
+        assert!(
+            (found.time - fine[0].time).abs() < 1e-6,
+            "{found:?} {fine:?}"
+        );
+        assert!(
+            (found.point.z - fine[0].point.z).abs() < 1e-4,
+            "{found:?} {fine:?}"
+        );
+        checked += 1;
+    }
+}
+
+#[test]
+fn the_lorenz_return_map_is_a_thin_curve() {
+    let mut simulation = lorenz(0.005);
+    let peaks: Vec<_> = maxima(&mut simulation, 400_000).collect();
+    let mut points: Vec<_> = return_map(&peaks).collect();
+    assert!(points.len() > 1000, "only {} points", points.len());
+    assert!(points
+        .iter()
+        .all(|&(z, next)| (28. ..50.).contains(&z) && (28. ..50.).contains(&next)));
+
+    // Close maxima are followed by close maxima, away from the tip of the cusp at least
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let neighbours: Vec<_> = points
+        .windows(2)
+        .filter(|pair| pair[1].0 - pair[0].0 < 0.01)
+        .collect();
+    let close = neighbours
+        .iter()
+        .filter(|pair| (pair[1].1 - pair[0].1).abs() < 0.5)
+        .count();
+    assert!(
+        close as f64 > 0.9 * neighbours.len() as f64,
+        "{close} of {} neighbours are close",
+        neighbours.len()
+    );
+}
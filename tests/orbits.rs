@@ -0,0 +1,66 @@
+use glam::DVec3;
+use lorenz_attractor::{
+    orbits::{PeriodicOrbit, Search},
+    poincare::{Direction, Plane, Section},
+    Attractor, DynamicalSystem, Simulation,
+};
+
+/// The orbits the classic Lorenz attractor comes close to within `max_crossings` crossings of
+/// the plane through its two fixed points
+fn lorenz_orbits(max_crossings: usize) -> (Attractor, Vec<PeriodicOrbit>) {
+    let system = Attractor::lorenz();
+    let mut simulation = Simulation::new(Box::new(system.clone()), system.initial_state());
+    simulation.dt = 0.002;
+    let search = Search {
+        section: Section {
+            plane: Plane::new(DVec3::Z, 27.),
+            direction: Direction::Down,
+        },
+        max_crossings,
+        transient: 10_000,
+        steps: 500_000,
+        closeness: 0.02,
+        attempts: 5,
+    };
+    (system, search.run(simulation).unwrap())
+}
+
+#[test]
+fn the_shortest_lorenz_orbits_have_their_known_periods() {
+    let (_, orbits) = lorenz_orbits(4);
+    let sequences: Vec<_> = orbits.iter().map(|orbit| orbit.sequence.as_str()).collect();
+    assert_eq!(sequences, ["LR", "LLR", "LRR", "LLLR", "LRRR", "LLRR"]);
+    assert!(orbits
+        .windows(2)
+        .all(|pair| pair[0].period <= pair[1].period + 1e-6));
+
+    let period = |sequence: &str| {
+        orbits
+            .iter()
+            .find(|orbit| orbit.sequence == sequence)
+            .unwrap()
+            .period
+    };
+    // As found by Viswanath for the parameters Lorenz used
+    assert!((period("LR") - 1.558652).abs() < 1e-6, "{}", period("LR"));
+    assert!((period("LLR") - 2.305907).abs() < 1e-6, "{}", period("LLR"));
+    // Turning the attractor half way around z swaps the wings, and maps orbits onto orbits
+    assert!((period("LLR") - period("LRR")).abs() < 1e-6);
+    assert!((period("LLLR") - period("LRRR")).abs() < 1e-6);
+}
+
+#[test]
+fn orbits_close_up_and_are_unstable() {
+    let (system, orbits) = lorenz_orbits(3);
+    assert!(!orbits.is_empty());
+    for orbit in &orbits {
+        let points = orbit.points(&system, 5000);
+        let (first, last) = (points[0], points[points.len() - 1]);
+        assert!(
+            first.distance(last) < 1e-4,
+            "{orbit:?} misses by {first} {last}"
+        );
+        assert!(orbit.multiplier > 1., "{orbit:?}");
+        assert!(orbit.sequence.contains('L') && orbit.sequence.contains('R'));
+    }
+}
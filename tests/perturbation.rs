@@ -0,0 +1,37 @@
+use glam::DVec3;
+use lorenz_attractor::perturbation::perturbations;
+
+#[test]
+fn offsets_have_the_requested_length() {
+    for size in [1, 2, 7, 100] {
+        let offsets: Vec<_> = perturbations(size, 1e-6).collect();
+        assert_eq!(offsets.len(), size);
+        for offset in offsets {
+            assert!((offset.length() - 1e-6).abs() < 1e-18, "{offset}");
+        }
+    }
+}
+
+#[test]
+fn offsets_point_in_different_directions() {
+    let offsets: Vec<_> = perturbations(50, 1.).collect();
+    for (i, a) in offsets.iter().enumerate() {
+        for b in &offsets[i + 1..] {
+            assert!(a.distance(*b) > 0.1, "{a} and {b} are too close");
+        }
+    }
+}
+
+#[test]
+fn an_ensemble_is_centred_on_its_reference_point() {
+    // The spiral only balances out once it winds around a few times
+    let reference = DVec3::new(1., -2., 20.);
+    let epsilon = 1e-3;
+    for size in [100, 1000] {
+        let mean = perturbations(size, epsilon)
+            .map(|offset| reference + offset)
+            .sum::<DVec3>()
+            / size as f64;
+        assert!(mean.distance(reference) < 1e-3 * epsilon, "{size}: {mean}");
+    }
+}
@@ -0,0 +1,98 @@
+use glam::DVec3;
+use lorenz_attractor::{
+    poincare::{Direction, Plane, Section},
+    Attractor, DynamicalSystem, Simulation,
+};
+
+/// The classic Lorenz system with the time step `dt`
+fn lorenz(dt: f64) -> Simulation {
+    let system = Attractor::lorenz();
+    let initial = system.initial_state();
+    let mut simulation = Simulation::new(Box::new(system), initial);
+    simulation.dt = dt;
+    simulation
+}
+
+/// The usual section of the Lorenz system, through its two fixed points at `z = rho - 1`
+fn section(direction: Direction) -> Section {
+    Section {
+        plane: Plane::new(DVec3::Z, 27.),
+        direction,
+    }
+}
+
+#[test]
+fn crossings_lie_on_the_plane_going_the_right_way() {
+    let mut simulation = lorenz(0.01);
+    let system = simulation.system.clone_box();
+    let section = section(Direction::Down);
+
+    let crossings: Vec<_> = section.crossings(&mut simulation, 5000).collect();
+    assert!(crossings.len() > 20, "only {} crossings", crossings.len());
+    for crossing in &crossings {
+        assert!((crossing.point.z - 27.).abs() < 1e-9, "{crossing:?}");
+        assert!(system.derivative(crossing.point).z < 0., "{crossing:?}");
+    }
+    assert!(crossings.windows(2).all(|pair| pair[0].time < pair[1].time));
+}
+
+#[test]
+fn both_directions_take_turns() {
+    let mut simulation = lorenz(0.01);
+    let section = section(Direction::Both);
+    let system = simulation.system.clone_box();
+
+    let going_up: Vec<_> = section
+        .crossings(&mut simulation, 5000)
+        .map(|crossing| system.derivative(crossing.point).z > 0.)
+        .collect();
+    assert!(going_up.windows(2).all(|pair| pair[0] != pair[1]));
+}
+
+#[test]
+fn crossings_are_pinned_down_within_the_step() {
+    let mut coarse = lorenz(0.01);
+    let section = section(Direction::Down);
+
+    let mut checked = 0;
+    while checked < 10 {
+        let from = (coarse.time, coarse.state);
+        coarse.step();
+        let Some(crossing) = section.crossing(&*coarse.system, from, (coarse.time, coarse.state))
+        else {
+            continue;
+        };
+
+        // The same step again in a thousand smaller ones
+        let mut fine = lorenz(0.00001);
+        fine.restart(from.1);
+        fine.time = from.0;
+        let fine: Vec<_> = section.crossings(&mut fine, 1000).collect();
+        assert_eq!(fine.len(), 1);
+        assert!(
+            (crossing.time - fine[0].time).abs() < 1e-7,
+            "{crossing:?} {fine:?}"
+        );
+        assert!(
+            crossing.point.distance(fine[0].point) < 1e-6,
+            "{crossing:?} {fine:?}"
+        );
+        checked += 1;
+    }
+}
+
+#[test]
+fn plane_axes_follow_the_coordinates() {
+    let p = DVec3::new(1., 2., 3.);
+    let along = |normal| Plane::new(normal, 0.).coordinates(p).to_array();
+    assert_eq!(along(DVec3::Z), [1., 2.]);
+    assert_eq!(along(DVec3::Y * 2.), [1., 3.]);
+    assert_eq!(along(-DVec3::X), [2., 3.]);
+
+    let plane = Plane::new(DVec3::new(1., 1., 1.), 3.);
+    let [u, v] = plane.axes();
+    for (a, b) in [(u, v), (u, plane.normal), (v, plane.normal)] {
+        assert!(a.dot(b).abs() < 1e-12);
+    }
+    assert!(plane.distance(DVec3::ONE).abs() < 1e-12);
+}
@@ -0,0 +1,75 @@
+use std::f64::consts::TAU;
+
+use glam::{DVec2, DVec3};
+use lorenz_attractor::{
+    power_spectrum::{fft, PowerSpectrum, Window},
+    Attractor, DynamicalSystem, Simulation,
+};
+
+#[test]
+fn the_fft_agrees_with_the_discrete_fourier_transform() {
+    let input: Vec<DVec2> = (0..64)
+        .map(|i| DVec2::new((i as f64 * 0.7).sin() + 0.1 * i as f64, (i * i % 7) as f64))
+        .collect();
+    let mut output = input.clone();
+    fft(&mut output);
+
+    for (k, value) in output.iter().enumerate() {
+        let expected: DVec2 = input
+            .iter()
+            .enumerate()
+            .map(|(i, x)| x.rotate(DVec2::from_angle(-TAU * (i * k) as f64 / 64.)))
+            .sum();
+        assert!(value.distance(expected) < 1e-9, "{k}: {value} {expected}");
+    }
+}
+
+#[test]
+fn a_sine_wave_puts_its_power_at_its_frequency() {
+    // 3 units of amplitude at 2.5, 100 samples to the unit of time
+    let interval = 0.01;
+    let samples: Vec<_> = (0..8192)
+        .map(|i| {
+            let t = i as f64 * interval;
+            DVec3::new(3. * (TAU * 2.5 * t).sin(), 1., 0.)
+        })
+        .collect();
+
+    for window in Window::ALL {
+        let spectrum = PowerSpectrum::new(&samples, interval, window, 1024).unwrap();
+        assert_eq!(spectrum.segments, 15);
+        assert!(
+            (spectrum.peaks().x - 2.5).abs() <= spectrum.resolution,
+            "{window}"
+        );
+
+        // The density adds up to the variance, half the squared amplitude
+        let variance = spectrum.density.iter().map(|d| d.x).sum::<f64>() * spectrum.resolution;
+        assert!((variance - 4.5).abs() < 0.05, "{window}: {variance}");
+        assert!(spectrum.density.iter().all(|d| d.y.abs() < 1e-20));
+    }
+}
+
+#[test]
+fn z_of_the_lorenz_attractor_peaks_at_the_frequency_of_its_loops() {
+    let system = Attractor::lorenz();
+    let mut simulation = Simulation::new(Box::new(system.clone()), system.initial_state());
+    for _ in 0..10_000 {
+        simulation.step();
+    }
+    let samples: Vec<_> = (0..100_000)
+        .map(|_| {
+            for _ in 0..10 {
+                simulation.step();
+            }
+            simulation.state
+        })
+        .collect();
+
+    let spectrum = PowerSpectrum::new(&samples, 0.01, Window::Hann, 4096).unwrap();
+    // A loop takes about 0.75 units of time
+    let peak = spectrum.peaks().z;
+    assert!((1.2..1.45).contains(&peak), "{peak}");
+    // x is spread over the slow switching between the wings instead
+    assert!(spectrum.peaks().x < 0.5);
+}
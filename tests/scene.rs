@@ -0,0 +1,151 @@
+//! Scenes are read by the binary, so they are checked by running it on them without a window
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+/// Lists the equilibria of the system the scene at `path` describes, which reads the whole
+/// scene without opening a window
+fn run(path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lorenz_attractor"))
+        .arg("--scene")
+        .arg(path)
+        .arg("equilibria")
+        .output()
+        .unwrap()
+}
+
+/// Writes `source` to a scene file of its own, named after `name`
+fn scene(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "lorenz_attractor_{}_{name}.toml",
+        std::process::id()
+    ));
+    fs::write(&path, source).unwrap();
+    path
+}
+
+/// The error the scene is rejected with
+fn rejected(name: &str, source: &str) -> String {
+    let path = scene(name, source);
+    let output = run(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(2), "{name} was accepted");
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn the_shipped_scenes_are_accepted() {
+    let scenes: Vec<_> = fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .collect();
+    assert!(!scenes.is_empty());
+
+    for path in scenes {
+        let output = run(&path);
+        assert!(
+            output.status.success(),
+            "{}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+#[test]
+fn every_table_the_scene_module_documents_is_accepted() {
+    let path = scene(
+        "documented",
+        r##"
+[system]
+name = "lorenz"
+rho = 28.0
+
+[integration]
+integrator = "rk4"
+dt = 0.001
+steps_per_frame = 50
+
+[color_map]
+hue = [25.0, 35.0]
+wings = [210.0, 25.0]
+
+[poincare]
+normal = [0.0, 0.0, 1.0]
+offset = "rho - 1"
+direction = "down"
+
+[orbits]
+max_crossings = 6
+selected = "LLR"
+
+[power_spectrum]
+window = "blackman"
+segment = 4096
+every = 10
+
+[[trajectory]]
+initial = [0.1, 0.0, 0.1]
+color = "#3080ff"
+"##,
+    );
+    let output = run(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn mistakes_in_a_scene_are_pointed_out() {
+    for (name, source, expected) in [
+        ("unknown", "[camera]\nzoom = 2.0", "unknown field `zoom`"),
+        (
+            "camera",
+            "[camera]\nposition = [nan, 0.0, 0.0]",
+            "camera.position must be a finite number",
+        ),
+        (
+            "sequence",
+            "[orbits]\nselected = \"LRX\"",
+            "`LRX` has to be made of L and R",
+        ),
+        (
+            "repeated",
+            "[orbits]\nselected = \"LRLR\"",
+            "`LRLR` goes around a shorter orbit more than once",
+        ),
+        (
+            "segment",
+            "[power_spectrum]\nsegment = 1000",
+            "power_spectrum.segment must be a power of two",
+        ),
+        (
+            "every",
+            "[power_spectrum]\nevery = 0",
+            "power_spectrum.every must be at least 1",
+        ),
+        (
+            "window",
+            "[power_spectrum]\nwindow = \"square\"",
+            "unknown window `square`",
+        ),
+        (
+            "trajectories",
+            "trajectory = []",
+            "at least one [[trajectory]]",
+        ),
+    ] {
+        let error = rejected(name, source);
+        assert!(error.contains(expected), "{name}: {error}");
+    }
+}
@@ -0,0 +1,61 @@
+use lorenz_attractor::{
+    symbols::{block_entropy, loops, run_lengths, Wing},
+    Attractor, DynamicalSystem, Simulation,
+};
+
+fn wings(symbols: &str) -> Vec<Wing> {
+    symbols
+        .chars()
+        .map(|symbol| {
+            if symbol == 'L' {
+                Wing::Left
+            } else {
+                Wing::Right
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn block_entropy_counts_the_distinct_blocks() {
+    // Every block of each length is as likely as any other
+    let alternating = wings(&"LR".repeat(500));
+    assert!((block_entropy(&alternating, 1) - 1.).abs() < 1e-12);
+    assert!((block_entropy(&alternating, 5) - 1.).abs() < 1e-12);
+
+    let constant = wings(&"L".repeat(100));
+    assert_eq!(block_entropy(&constant, 3), 0.);
+    assert_eq!(block_entropy(&constant, 101), 0.);
+}
+
+#[test]
+fn run_lengths_leave_out_the_runs_cut_short() {
+    let histogram = run_lengths(&wings("LLRLLLRRLRRRRL"));
+    // R, LLL, RR, L and RRRR are whole, the first LL and the last L are not
+    assert_eq!(histogram, [0, 2, 1, 1, 1]);
+    assert!(run_lengths(&wings("LLLRRR")).is_empty());
+}
+
+#[test]
+fn the_lorenz_attractor_switches_wings_unpredictably() {
+    let system = Attractor::lorenz();
+    let mut simulation = Simulation::new(Box::new(system.clone()), system.initial_state());
+    for _ in 0..20_000 {
+        simulation.step();
+    }
+    let loops: Vec<_> = loops(&mut simulation, 2_000_000).collect();
+    for (maximum, wing) in &loops {
+        assert_eq!(*wing, Wing::of(maximum.point));
+    }
+    let wings: Vec<Wing> = loops.iter().map(|&(_, wing)| wing).collect();
+
+    // Each longer block adds close to a whole bit, short of it as not every block can follow
+    // every other
+    let added = block_entropy(&wings, 6) - block_entropy(&wings, 5);
+    assert!(0.85 < added && added < 1., "{added}");
+
+    // Staying on a wing for one more loop is about half as likely each time
+    let histogram = run_lengths(&wings);
+    let ratio = histogram[2] as f64 / histogram[1] as f64;
+    assert!(0.35 < ratio && ratio < 0.65, "{histogram:?}");
+}